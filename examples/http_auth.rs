@@ -109,10 +109,11 @@ fn main() -> Result<()> {
     let test_token = generate_test_token("user-123", "tenant-456");
     eprintln!("\n=== TEST TOKEN (valid for 1 hour) ===");
     eprintln!("{}", test_token);
-    eprintln!("\nTest with:");
+    eprintln!("\nTest with (after an `initialize` call to obtain a session id):");
     eprintln!("curl -X POST http://{}/mcp \\", addr);
     eprintln!("  -H \"Content-Type: application/json\" \\");
     eprintln!("  -H \"Authorization: Bearer {}\" \\", test_token);
+    eprintln!("  -H \"Mcp-Session-Id: <session id>\" \\");
     eprintln!(
         "  -d '{{\"jsonrpc\":\"2.0\",\"id\":1,\"method\":\"tools/call\",\"params\":{{\"name\":\"whoami\"}}}}'"
     );
@@ -125,11 +125,17 @@ impl Tool<AppContext> for CounterResetTool {
 fn main() -> Result<()> {
     let addr = "127.0.0.1:3000";
 
-    eprintln!("Test with:");
+    eprintln!("Test with (initialize first, then reuse the Mcp-Session-Id header):");
+    eprintln!(
+        "  curl -i -X POST http://{}/mcp -H 'Content-Type: application/json' \\",
+        addr
+    );
+    eprintln!("    -d '{{\"jsonrpc\":\"2.0\",\"id\":0,\"method\":\"initialize\"}}'");
     eprintln!(
         "  curl -X POST http://{}/mcp -H 'Content-Type: application/json' \\",
         addr
     );
+    eprintln!("    -H 'Mcp-Session-Id: <id from above>' \\");
     eprintln!(
         "    -d '{{\"jsonrpc\":\"2.0\",\"id\":1,\"method\":\"tools/call\",\"params\":{{\"name\":\"echo\",\"arguments\":{{\"message\":\"hello\"}}}}}}'"
     );
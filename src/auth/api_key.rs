@@ -0,0 +1,111 @@
+//! Static API Keys
+//!
+//! Long-lived keys for services and scripts, each mapped to fixed claims.
+
+use super::authenticator::{AuthError, AuthRequest, Authenticator};
+use super::jwt::Claims;
+use ring::digest::{SHA256, digest};
+use std::collections::HashMap;
+
+/// Header carrying the key unless configured otherwise
+const DEFAULT_HEADER: &str = "X-API-Key";
+
+/// Authenticates callers by a static key in a request header
+///
+/// Only SHA-256 digests of the keys are kept, and lookups compare digests,
+/// so the time taken says nothing about the keys themselves.
+///
+/// # Example
+/// ```ignore
+/// let keys = ApiKeys::new().key(
+///     std::env::var("CI_API_KEY")?,
+///     Claims {
+///         sub: "ci".into(),
+///         scope: Some("tools:read".into()),
+///         ..Default::default()
+///     },
+/// );
+/// ```
+#[derive(Debug, Clone)]
+pub struct ApiKeys {
+    header: String,
+    keys: HashMap<Vec<u8>, Claims>,
+}
+
+impl Default for ApiKeys {
+    fn default() -> Self {
+        Self {
+            header: DEFAULT_HEADER.to_string(),
+            keys: HashMap::new(),
+        }
+    }
+}
+
+impl ApiKeys {
+    /// Create with no keys, reading them from `X-API-Key`
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Read keys from this header instead
+    pub fn with_header(mut self, name: impl Into<String>) -> Self {
+        self.header = name.into();
+        self
+    }
+
+    /// Accept `key`, identifying the caller with `claims`
+    pub fn key(mut self, key: impl AsRef<[u8]>, claims: Claims) -> Self {
+        self.keys.insert(fingerprint(key.as_ref()), claims);
+        self
+    }
+}
+
+impl Authenticator for ApiKeys {
+    fn authenticate(&self, request: &AuthRequest<'_>) -> Result<Option<Claims>, AuthError> {
+        let Some(key) = request.header(&self.header) else {
+            return Ok(None);
+        };
+        match self.keys.get(&fingerprint(key.trim().as_bytes())) {
+            Some(claims) => Ok(Some(claims.clone())),
+            None => Err(AuthError::Rejected("Unknown API key".into())),
+        }
+    }
+}
+
+fn fingerprint(key: &[u8]) -> Vec<u8> {
+    digest(&SHA256, key).as_ref().to_vec()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::auth::authenticator::test_request as request;
+
+    #[test]
+    fn test_api_keys() {
+        let ci = Claims {
+            sub: "ci".into(),
+            scope: Some("read".into()),
+            ..Default::default()
+        };
+        let keys = ApiKeys::new()
+            .key("k-123", ci)
+            .key(b"k-456", Claims::default());
+
+        let headers = [("x-api-key".to_string(), "k-123".to_string())];
+        let claims = keys.authenticate(&request(&headers)).unwrap().unwrap();
+        assert_eq!(claims.user_id(), "ci");
+        assert!(claims.has_scope("read"));
+
+        let headers = [("X-API-Key".to_string(), "k-000".to_string())];
+        assert_eq!(
+            keys.authenticate(&request(&headers)).unwrap_err(),
+            AuthError::Rejected("Unknown API key".into())
+        );
+        assert!(matches!(keys.authenticate(&request(&[])), Ok(None)));
+
+        let keys = keys.with_header("X-Service-Key");
+        let headers = [("X-Service-Key".to_string(), "k-456".to_string())];
+        assert!(keys.authenticate(&request(&headers)).unwrap().is_some());
+    }
+}
@@ -0,0 +1,308 @@
+//! Pluggable Authentication
+//!
+//! An `Authenticator` turns the credentials on a request into `Claims`.
+//! `JwtValidator` is one; API keys, token introspection, client
+//! certificates and signed requests are others, and `AuthChain` tries
+//! several in turn.
+
+use super::jwt::{Claims, JwtError, JwtValidator};
+use std::net::SocketAddr;
+use thiserror::Error;
+
+/// Why an authenticator refused a request
+#[derive(Error, Debug, Clone, PartialEq)]
+pub enum AuthError {
+    /// Credentials were sent, but in an unusable form (`400`)
+    #[error("Malformed credentials: {0}")]
+    Malformed(String),
+
+    /// Credentials were sent but are not valid (`401`)
+    #[error("{0}")]
+    Rejected(String),
+}
+
+impl From<JwtError> for AuthError {
+    fn from(error: JwtError) -> Self {
+        match error {
+            JwtError::InvalidFormat => AuthError::Malformed(error.to_string()),
+            other => AuthError::Rejected(other.to_string()),
+        }
+    }
+}
+
+/// The parts of a request an authenticator can inspect
+#[derive(Debug, Clone, Copy)]
+pub struct AuthRequest<'a> {
+    /// Upper-case HTTP method, e.g. `POST`
+    pub method: &'a str,
+    /// Request target: the path plus any query string
+    pub url: &'a str,
+    pub headers: &'a [(String, String)],
+    /// Request body; empty for a WebSocket handshake
+    pub body: &'a [u8],
+    /// Address of the connected client (the proxy, when behind one)
+    pub peer: Option<SocketAddr>,
+}
+
+impl AuthRequest<'_> {
+    /// Find a header by name (case-insensitive)
+    pub fn header(&self, name: &str) -> Option<&str> {
+        self.headers
+            .iter()
+            .find(|(n, _)| n.eq_ignore_ascii_case(name))
+            .map(|(_, v)| v.as_str())
+    }
+
+    /// The token from an `Authorization: Bearer` header
+    ///
+    /// Following RFC 6750, another scheme is no bearer token at all, while
+    /// `Bearer` without a single token is malformed.
+    pub fn bearer_token(&self) -> Result<Option<&str>, AuthError> {
+        let Some(header) = self.header("Authorization") else {
+            return Ok(None);
+        };
+        let header = header.trim();
+        let (scheme, token) = header.split_once(' ').unwrap_or((header, ""));
+        if !scheme.eq_ignore_ascii_case("Bearer") {
+            return Ok(None);
+        }
+        let token = token.trim();
+        if token.is_empty() || token.contains(' ') {
+            return Err(AuthError::Malformed("expected 'Bearer <token>'".into()));
+        }
+        Ok(Some(token))
+    }
+}
+
+/// Identifies the caller of a request
+///
+/// Return `Ok(None)` when the request carries no credentials of this kind,
+/// so other authenticators in an `AuthChain` get a chance, and an error
+/// when it carries credentials that don't check out.
+///
+/// # Example
+/// ```ignore
+/// struct Internal;
+///
+/// impl Authenticator for Internal {
+///     fn authenticate(&self, request: &AuthRequest<'_>) -> Result<Option<Claims>, AuthError> {
+///         let is_local = request.peer.is_some_and(|peer| peer.ip().is_loopback());
+///         Ok(is_local.then(|| Claims {
+///             sub: "local".into(),
+///             ..Default::default()
+///         }))
+///     }
+/// }
+/// ```
+pub trait Authenticator: Send + Sync {
+    /// Check the request's credentials and return the caller's claims
+    fn authenticate(&self, request: &AuthRequest<'_>) -> Result<Option<Claims>, AuthError>;
+
+    /// True if `authenticate` must see this request's body
+    ///
+    /// Decided from the headers alone, with `request.body` still empty.
+    /// Requests are authenticated before their body is read unless this
+    /// says otherwise, so unauthenticated callers can't make the server
+    /// wait on a large or slow upload.
+    fn needs_body(&self, request: &AuthRequest<'_>) -> bool {
+        let _ = request;
+        false
+    }
+
+    /// True if callers identify themselves with bearer tokens
+    ///
+    /// Requests without credentials only get a `Bearer` challenge when
+    /// this is true.
+    fn uses_bearer_tokens(&self) -> bool {
+        false
+    }
+}
+
+impl Authenticator for JwtValidator {
+    fn authenticate(&self, request: &AuthRequest<'_>) -> Result<Option<Claims>, AuthError> {
+        match request.bearer_token()? {
+            Some(token) => Ok(Some(self.validate(token)?)),
+            None => Ok(None),
+        }
+    }
+
+    fn uses_bearer_tokens(&self) -> bool {
+        true
+    }
+}
+
+/// Authenticators tried in order until one identifies the caller
+///
+/// The first success wins. If none succeeds, the first error is returned,
+/// so a JWT validator can sit in front of token introspection and opaque
+/// tokens still get through.
+///
+/// # Example
+/// ```ignore
+/// let auth = AuthChain::new()
+///     .with(JwtValidator::jwks(Jwks::from_url_with(jwks_url, https_get)))
+///     .with(ApiKeys::new().key(ci_key, ci_claims));
+///
+/// HttpServer::new(config).serve_with_auth(addr, auth, make_context)?;
+/// ```
+#[derive(Default)]
+pub struct AuthChain {
+    authenticators: Vec<Box<dyn Authenticator>>,
+}
+
+impl AuthChain {
+    /// Create an empty chain, which identifies nobody
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Try `authenticator` after those already added
+    pub fn with(mut self, authenticator: impl Authenticator + 'static) -> Self {
+        self.authenticators.push(Box::new(authenticator));
+        self
+    }
+}
+
+impl Authenticator for AuthChain {
+    fn authenticate(&self, request: &AuthRequest<'_>) -> Result<Option<Claims>, AuthError> {
+        let mut first_error = None;
+        for authenticator in &self.authenticators {
+            match authenticator.authenticate(request) {
+                Ok(Some(claims)) => return Ok(Some(claims)),
+                Ok(None) => {}
+                Err(e) => {
+                    log::debug!("Authenticator refused request: {}", e);
+                    first_error.get_or_insert(e);
+                }
+            }
+        }
+        first_error.map_or(Ok(None), Err)
+    }
+
+    fn needs_body(&self, request: &AuthRequest<'_>) -> bool {
+        self.authenticators
+            .iter()
+            .any(|authenticator| authenticator.needs_body(request))
+    }
+
+    fn uses_bearer_tokens(&self) -> bool {
+        self.authenticators
+            .iter()
+            .any(|authenticator| authenticator.uses_bearer_tokens())
+    }
+}
+
+/// A bodiless `POST /mcp` with the given headers, for the authenticator tests
+#[cfg(test)]
+pub(crate) fn test_request(headers: &[(String, String)]) -> AuthRequest<'_> {
+    AuthRequest {
+        method: "POST",
+        url: "/mcp",
+        headers,
+        body: b"",
+        peer: None,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::test_request as request;
+    use super::*;
+
+    const SECRET: &[u8] = b"test-secret-key";
+
+    fn authorization(value: &str) -> Vec<(String, String)> {
+        vec![("authorization".to_string(), value.to_string())]
+    }
+
+    fn token(sub: &str) -> String {
+        let claims = serde_json::json!({ "sub": sub, "exp": u64::MAX / 2 });
+        jsonwebtoken::encode(
+            &jsonwebtoken::Header::default(),
+            &claims,
+            &jsonwebtoken::EncodingKey::from_secret(SECRET),
+        )
+        .unwrap()
+    }
+
+    /// Accepts one fixed header value
+    struct Fixed(&'static str);
+    impl Authenticator for Fixed {
+        fn authenticate(&self, request: &AuthRequest<'_>) -> Result<Option<Claims>, AuthError> {
+            match request.header("X-Fixed") {
+                Some(value) if value == self.0 => Ok(Some(Claims {
+                    sub: value.to_string(),
+                    ..Default::default()
+                })),
+                Some(_) => Err(AuthError::Rejected("wrong value".into())),
+                None => Ok(None),
+            }
+        }
+    }
+
+    #[test]
+    fn test_bearer_token() {
+        let headers = authorization("bearer  abc ");
+        assert_eq!(request(&headers).bearer_token(), Ok(Some("abc")));
+
+        let headers = authorization("Basic YWxpY2U6c2VjcmV0");
+        assert_eq!(request(&headers).bearer_token(), Ok(None));
+        assert_eq!(request(&[]).bearer_token(), Ok(None));
+
+        for malformed in ["Bearer", "Bearer ", "Bearer a b"] {
+            let headers = authorization(malformed);
+            assert!(matches!(
+                request(&headers).bearer_token(),
+                Err(AuthError::Malformed(_))
+            ));
+        }
+    }
+
+    #[test]
+    fn test_jwt_authenticator() {
+        let validator = JwtValidator::hs256(SECRET);
+
+        let headers = authorization(&format!("Bearer {}", token("alice")));
+        let claims = validator.authenticate(&request(&headers)).unwrap().unwrap();
+        assert_eq!(claims.user_id(), "alice");
+
+        assert!(matches!(validator.authenticate(&request(&[])), Ok(None)));
+        let headers = authorization("Bearer not-a-jwt");
+        assert!(matches!(
+            validator.authenticate(&request(&headers)),
+            Err(AuthError::Rejected(_))
+        ));
+    }
+
+    #[test]
+    fn test_auth_chain() {
+        let chain = AuthChain::new()
+            .with(JwtValidator::hs256(SECRET))
+            .with(Fixed("service"));
+
+        let mut headers = authorization(&format!("Bearer {}", token("alice")));
+        let claims = chain.authenticate(&request(&headers)).unwrap().unwrap();
+        assert_eq!(claims.user_id(), "alice");
+
+        // A later authenticator succeeds despite an earlier error
+        headers = authorization("Bearer opaque");
+        headers.push(("X-Fixed".into(), "service".into()));
+        let claims = chain.authenticate(&request(&headers)).unwrap().unwrap();
+        assert_eq!(claims.user_id(), "service");
+
+        // Otherwise the first error is reported
+        headers[1].1 = "other".into();
+        let error = chain.authenticate(&request(&headers)).unwrap_err();
+        assert!(error.to_string().contains("Token validation failed"));
+
+        assert!(matches!(chain.authenticate(&request(&[])), Ok(None)));
+        assert!(matches!(
+            AuthChain::new().authenticate(&request(&headers)),
+            Ok(None)
+        ));
+
+        // Bearer challenges only if some member takes bearer tokens
+        assert!(chain.uses_bearer_tokens());
+        assert!(!AuthChain::new().with(Fixed("service")).uses_bearer_tokens());
+    }
+}
@@ -0,0 +1,148 @@
+//! Client Certificate Subjects
+//!
+//! Maps the subject of a verified TLS client certificate to claims. The
+//! built-in HTTPS listener (`TlsConfig`) can't request or verify client
+//! certificates, so this only works behind a proxy (nginx, Envoy, a load
+//! balancer) that terminates mutual TLS and forwards the verified subject
+//! in a header.
+
+use super::authenticator::{AuthError, AuthRequest, Authenticator};
+use super::jwt::Claims;
+use std::collections::HashMap;
+use std::net::IpAddr;
+
+/// Authenticates callers by the client certificate subject a proxy forwards
+///
+/// The header is only believed from proxies added with `trust_proxy`;
+/// nothing is trusted by default, not even loopback, since other local
+/// processes could connect too. From anyone else the header is refused,
+/// as a client could set it itself. The proxy must also strip any copy of
+/// the header sent by the client, or a client without a certificate could
+/// pass its own subject through it.
+///
+/// # Example
+/// ```ignore
+/// // nginx: proxy_set_header X-Client-Subject $ssl_client_s_dn;
+/// let certs = ClientCertificates::new("X-Client-Subject")
+///     .trust_proxy("10.0.0.5".parse()?)
+///     .subject("CN=billing,O=Example", Claims {
+///         sub: "billing-service".into(),
+///         ..Default::default()
+///     });
+/// ```
+#[derive(Debug, Clone)]
+pub struct ClientCertificates {
+    header: String,
+    trusted_proxies: Vec<IpAddr>,
+    subjects: HashMap<String, Claims>,
+}
+
+impl ClientCertificates {
+    /// Read the verified subject from `header`, set by a proxy added with `trust_proxy`
+    pub fn new(header: impl Into<String>) -> Self {
+        Self {
+            header: header.into(),
+            trusted_proxies: Vec::new(),
+            subjects: HashMap::new(),
+        }
+    }
+
+    /// Believe the header from a proxy at `addr`
+    ///
+    /// The proxy must overwrite or remove the header on every request it
+    /// forwards; for nginx, `proxy_set_header` does that.
+    pub fn trust_proxy(mut self, addr: IpAddr) -> Self {
+        self.trusted_proxies.push(addr);
+        self
+    }
+
+    /// Identify clients whose certificate has this subject with `claims`
+    pub fn subject(mut self, subject: impl Into<String>, claims: Claims) -> Self {
+        self.subjects.insert(subject.into(), claims);
+        self
+    }
+}
+
+impl Authenticator for ClientCertificates {
+    fn authenticate(&self, request: &AuthRequest<'_>) -> Result<Option<Claims>, AuthError> {
+        let Some(subject) = request.header(&self.header) else {
+            return Ok(None);
+        };
+        let trusted = request
+            .peer
+            .is_some_and(|peer| self.trusted_proxies.contains(&peer.ip()));
+        if !trusted {
+            log::warn!(
+                "Ignoring {} from untrusted peer {:?}",
+                self.header,
+                request.peer
+            );
+            return Err(AuthError::Rejected(
+                "Client certificate not forwarded by a trusted proxy".into(),
+            ));
+        }
+
+        match self.subjects.get(subject.trim()) {
+            Some(claims) => Ok(Some(claims.clone())),
+            None => Err(AuthError::Rejected(format!(
+                "Unknown client certificate: {}",
+                subject.trim()
+            ))),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::auth::authenticator::test_request;
+
+    fn request<'a>(headers: &'a [(String, String)], peer: &str) -> AuthRequest<'a> {
+        AuthRequest {
+            peer: Some(peer.parse().unwrap()),
+            ..test_request(headers)
+        }
+    }
+
+    #[test]
+    fn test_client_certificates() {
+        let billing = Claims {
+            sub: "billing".into(),
+            ..Default::default()
+        };
+        let certs = ClientCertificates::new("X-Client-Subject")
+            .trust_proxy("10.0.0.5".parse().unwrap())
+            .trust_proxy("::1".parse().unwrap())
+            .subject("CN=billing,O=Example", billing);
+
+        let headers = [(
+            "x-client-subject".to_string(),
+            "CN=billing,O=Example".to_string(),
+        )];
+        for proxy in ["[::1]:4000", "10.0.0.5:4000"] {
+            let claims = certs.authenticate(&request(&headers, proxy)).unwrap();
+            assert_eq!(claims.unwrap().user_id(), "billing");
+        }
+
+        // Anyone else could have set the header themselves; loopback too,
+        // unless trusted explicitly
+        for peer in ["203.0.113.9:4000", "127.0.0.1:4000"] {
+            let error = certs.authenticate(&request(&headers, peer)).unwrap_err();
+            assert!(error.to_string().contains("trusted proxy"));
+        }
+
+        let headers = [("X-Client-Subject".to_string(), "CN=other".to_string())];
+        let error = certs
+            .authenticate(&request(&headers, "10.0.0.5:4000"))
+            .unwrap_err();
+        assert_eq!(
+            error,
+            AuthError::Rejected("Unknown client certificate: CN=other".into())
+        );
+
+        assert!(matches!(
+            certs.authenticate(&request(&[], "10.0.0.5:4000")),
+            Ok(None)
+        ));
+    }
+}
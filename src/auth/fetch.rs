@@ -0,0 +1,175 @@
+//! Plain HTTP Client
+//!
+//! Just enough HTTP/1.0 to reach an identity provider on a trusted network
+//! (JWKS, token introspection) without pulling in an HTTP client crate.
+
+use std::io::{Read, Write};
+use std::net::{SocketAddr, TcpStream, ToSocketAddrs};
+use std::time::{Duration, Instant};
+
+/// Time allowed for a whole request to the identity provider, connect included
+pub(super) const DEFAULT_TIMEOUT: Duration = Duration::from_secs(10);
+
+/// Largest response accepted; JWKS documents and introspection answers are small
+const MAX_RESPONSE_SIZE: usize = 1024 * 1024;
+
+/// Send a request to an `http://` URL and return the body of a `200` response
+///
+/// The request fails if it isn't answered within `timeout` or the response
+/// is larger than `MAX_RESPONSE_SIZE`. Name resolution is up to the system
+/// resolver and not covered by `timeout`; use addresses or `/etc/hosts`
+/// names where that matters.
+pub(super) fn http(
+    method: &str,
+    url: &str,
+    headers: &[(&str, String)],
+    body: &[u8],
+    timeout: Duration,
+) -> Result<Vec<u8>, String> {
+    let deadline = Instant::now() + timeout;
+    let rest = url
+        .strip_prefix("http://")
+        .ok_or("only http:// is built in")?;
+    let (authority, path) = match rest.find('/') {
+        Some(i) => rest.split_at(i),
+        None => (rest, "/"),
+    };
+    let addr = if authority
+        .rsplit(']')
+        .next()
+        .is_some_and(|h| h.contains(':'))
+    {
+        authority.to_string()
+    } else {
+        format!("{}:80", authority)
+    };
+
+    let mut request = format!(
+        "{} {} HTTP/1.0\r\nHost: {}\r\nAccept: application/json\r\nConnection: close\r\n",
+        method, path, authority
+    );
+    for (name, value) in headers {
+        request.push_str(&format!("{}: {}\r\n", name, value));
+    }
+    if !body.is_empty() {
+        request.push_str(&format!("Content-Length: {}\r\n", body.len()));
+    }
+    request.push_str("\r\n");
+
+    let mut stream = connect(&addr, deadline)?;
+    set_timeouts(&stream, deadline)?;
+    stream
+        .write_all(request.as_bytes())
+        .and_then(|_| stream.write_all(body))
+        .map_err(|e| e.to_string())?;
+
+    // Read until the server closes, within the deadline and size limit
+    let mut response = Vec::new();
+    let mut buf = [0; 8192];
+    loop {
+        set_timeouts(&stream, deadline)?;
+        let n = stream.read(&mut buf).map_err(|e| e.to_string())?;
+        if n == 0 {
+            break;
+        }
+        response.extend_from_slice(&buf[..n]);
+        if response.len() > MAX_RESPONSE_SIZE {
+            return Err(format!("response larger than {} bytes", MAX_RESPONSE_SIZE));
+        }
+    }
+
+    let split = response
+        .windows(4)
+        .position(|w| w == b"\r\n\r\n")
+        .ok_or("malformed HTTP response")?;
+    let head = String::from_utf8_lossy(&response[..split]);
+    let status = head.split_whitespace().nth(1).unwrap_or_default();
+    if status != "200" {
+        return Err(format!("HTTP status {}", status));
+    }
+    Ok(response.split_off(split + 4))
+}
+
+/// Connect to the first address `addr` resolves to that answers before `deadline`
+fn connect(addr: &str, deadline: Instant) -> Result<TcpStream, String> {
+    let addrs: Vec<SocketAddr> = addr.to_socket_addrs().map_err(|e| e.to_string())?.collect();
+    let mut last_error = format!("{} did not resolve", addr);
+    for addr in addrs {
+        let remaining = remaining(deadline)?;
+        match TcpStream::connect_timeout(&addr, remaining) {
+            Ok(stream) => return Ok(stream),
+            Err(e) => last_error = e.to_string(),
+        }
+    }
+    Err(last_error)
+}
+
+/// Limit the next read or write to the time left
+fn set_timeouts(stream: &TcpStream, deadline: Instant) -> Result<(), String> {
+    let remaining = remaining(deadline)?;
+    stream
+        .set_read_timeout(Some(remaining))
+        .and_then(|_| stream.set_write_timeout(Some(remaining)))
+        .map_err(|e| e.to_string())
+}
+
+fn remaining(deadline: Instant) -> Result<Duration, String> {
+    match deadline.checked_duration_since(Instant::now()) {
+        Some(remaining) if !remaining.is_zero() => Ok(remaining),
+        _ => Err("timed out".into()),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::io::BufRead;
+    use std::net::TcpListener;
+    use std::thread;
+
+    /// Answer one request with whatever `respond` writes
+    fn stub(respond: impl FnOnce(&mut TcpStream) + Send + 'static) -> String {
+        let listener = TcpListener::bind("127.0.0.1:0").unwrap();
+        let addr = listener.local_addr().unwrap();
+        thread::spawn(move || {
+            let (stream, _) = listener.accept().unwrap();
+            let mut reader = std::io::BufReader::new(stream);
+            let mut line = String::new();
+            while line != "\r\n" {
+                line.clear();
+                reader.read_line(&mut line).unwrap();
+            }
+            respond(reader.get_mut());
+        });
+        format!("http://{}/", addr)
+    }
+
+    #[test]
+    fn test_fetch_deadline() {
+        // Trickles bytes forever: each read succeeds, but the whole request can't
+        let url = stub(|stream| {
+            let _ = stream.write_all(b"HTTP/1.0 200 OK\r\n\r\n");
+            while stream.write_all(b" ").is_ok() {
+                thread::sleep(Duration::from_millis(20));
+            }
+        });
+        let started = Instant::now();
+        let error = http("GET", &url, &[], &[], Duration::from_millis(300)).unwrap_err();
+        assert!(started.elapsed() < Duration::from_secs(2), "{}", error);
+    }
+
+    #[test]
+    fn test_fetch_size_limit() {
+        let url = stub(|stream| {
+            let _ = stream.write_all(b"HTTP/1.0 200 OK\r\n\r\n");
+            let chunk = vec![b'x'; 64 * 1024];
+            for _ in 0..32 {
+                if stream.write_all(&chunk).is_err() {
+                    break;
+                }
+            }
+        });
+        let error = http("GET", &url, &[], &[], DEFAULT_TIMEOUT).unwrap_err();
+        assert!(error.contains("larger than"), "{}", error);
+    }
+}
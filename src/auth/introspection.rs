@@ -0,0 +1,457 @@
+//! Token Introspection
+//!
+//! Validates opaque bearer tokens by asking the authorization server about
+//! them (RFC 7662), caching the answers for a short while.
+
+use super::authenticator::{AuthError, AuthRequest, Authenticator};
+use super::fetch;
+use super::jwt::{ClaimMapping, Claims};
+use base64::Engine;
+use base64::engine::general_purpose::STANDARD;
+use ring::digest::{SHA256, digest};
+use serde_json::{Map, Value};
+use std::collections::HashMap;
+use std::sync::{Arc, Mutex};
+use std::time::{Duration, Instant, SystemTime, UNIX_EPOCH};
+
+/// How long an active token is trusted before asking again
+const DEFAULT_CACHE_TTL: Duration = Duration::from_secs(60);
+
+/// How long a refused token stays refused without asking again
+const DEFAULT_NEGATIVE_CACHE_TTL: Duration = Duration::from_secs(10);
+
+/// Most answers cached at once, so random tokens can't grow the cache unbounded
+const MAX_CACHE_ENTRIES: usize = 10_000;
+
+/// Time allowed for the built-in client's request; the serve loop waits on it
+const DEFAULT_TIMEOUT: Duration = Duration::from_secs(5);
+
+/// Asks about a token and returns the raw introspection response
+pub type IntrospectionFetcher = dyn Fn(&str) -> Result<Vec<u8>, AuthError> + Send + Sync;
+
+/// Authenticates bearer tokens through an introspection endpoint
+///
+/// The response's members become the claims, through the same
+/// `ClaimMapping` used for JWTs. Tokens the endpoint reports inactive or
+/// expired are refused, as are tokens for another issuer or audience when
+/// those are required.
+///
+/// # Example
+/// ```ignore
+/// let introspection = Introspection::new(
+///     "http://auth.internal/oauth2/introspect",
+///     "mcp-server",
+///     &client_secret,
+/// )
+/// .with_audience("https://mcp.example.com")
+/// .with_cache_ttl(Duration::from_secs(30));
+/// ```
+pub struct Introspection {
+    source: Source,
+    mapping: ClaimMapping,
+    issuer: Option<String>,
+    audience: Option<String>,
+    cache_ttl: Duration,
+    negative_cache_ttl: Duration,
+    /// Answers by token SHA-256 digest, with when to ask again
+    cache: Mutex<HashMap<Vec<u8>, (Answer, Instant)>>,
+}
+
+/// What the endpoint said about a token
+type Answer = Result<Claims, AuthError>;
+
+/// Where introspection answers come from
+enum Source {
+    /// The built-in client, with the `Authorization` value to send
+    Endpoint {
+        url: String,
+        credentials: String,
+        timeout: Duration,
+    },
+    Custom(Arc<IntrospectionFetcher>),
+}
+
+impl Introspection {
+    /// Introspect at an `http://` endpoint, authenticating with client credentials
+    ///
+    /// The built-in client speaks plain HTTP only. For `https://` endpoints,
+    /// supply a fetcher backed by your HTTP client with `with_fetcher`.
+    pub fn new(endpoint: impl Into<String>, client_id: &str, client_secret: &str) -> Self {
+        let credentials = format!(
+            "Basic {}",
+            STANDARD.encode(format!(
+                "{}:{}",
+                form_encode(client_id),
+                form_encode(client_secret)
+            ))
+        );
+        Self::from_source(Source::Endpoint {
+            url: endpoint.into(),
+            credentials,
+            timeout: DEFAULT_TIMEOUT,
+        })
+    }
+
+    /// Introspect with a custom fetcher, given the token
+    pub fn with_fetcher<F>(fetcher: F) -> Self
+    where
+        F: Fn(&str) -> Result<Vec<u8>, AuthError> + Send + Sync + 'static,
+    {
+        Self::from_source(Source::Custom(Arc::new(fetcher)))
+    }
+
+    fn from_source(source: Source) -> Self {
+        Self {
+            source,
+            mapping: ClaimMapping::default(),
+            issuer: None,
+            audience: None,
+            cache_ttl: DEFAULT_CACHE_TTL,
+            negative_cache_ttl: DEFAULT_NEGATIVE_CACHE_TTL,
+            cache: Mutex::new(HashMap::new()),
+        }
+    }
+
+    /// Require a specific issuer (`iss`)
+    pub fn with_issuer(mut self, issuer: &str) -> Self {
+        self.issuer = Some(issuer.to_string());
+        self
+    }
+
+    /// Require a specific audience (`aud`)
+    pub fn with_audience(mut self, audience: &str) -> Self {
+        self.audience = Some(audience.to_string());
+        self
+    }
+
+    /// Give the built-in client this long to get an answer (default: 5 seconds)
+    ///
+    /// Has no effect with a custom fetcher, which bounds its own requests.
+    pub fn with_timeout(mut self, timeout: Duration) -> Self {
+        if let Source::Endpoint { timeout: t, .. } = &mut self.source {
+            *t = timeout;
+        }
+        self
+    }
+
+    /// Trust an active token this long before asking again (default: 60 seconds)
+    ///
+    /// A revoked token keeps working until its cache entry expires; zero
+    /// asks on every request.
+    pub fn with_cache_ttl(mut self, ttl: Duration) -> Self {
+        self.cache_ttl = ttl;
+        self
+    }
+
+    /// Keep refusing a token reported inactive this long before asking again
+    /// (default: 10 seconds)
+    ///
+    /// Failures to reach the endpoint are not cached.
+    pub fn with_negative_cache_ttl(mut self, ttl: Duration) -> Self {
+        self.negative_cache_ttl = ttl;
+        self
+    }
+
+    /// Read user, tenant and scopes from other response members
+    pub fn with_claim_mapping(mut self, mapping: ClaimMapping) -> Self {
+        self.mapping = mapping;
+        self
+    }
+
+    /// Ask the endpoint about `token`, returning its claims if active
+    pub fn introspect(&self, token: &str) -> Result<Claims, AuthError> {
+        let key = digest(&SHA256, token.as_bytes()).as_ref().to_vec();
+        {
+            let cache = self.cache.lock().unwrap_or_else(|e| e.into_inner());
+            if let Some((answer, _)) = cache.get(&key).filter(|(_, until)| *until > Instant::now())
+            {
+                return answer.clone();
+            }
+        }
+
+        let response = self.fetch(token)?;
+        let (answer, ttl) = match self.claims(&response) {
+            Ok((claims, lifetime)) => {
+                let ttl = lifetime.map_or(self.cache_ttl, |lifetime| lifetime.min(self.cache_ttl));
+                (Ok(claims), ttl)
+            }
+            Err(error) => (Err(error), self.negative_cache_ttl),
+        };
+        if !ttl.is_zero() {
+            self.remember(key, answer.clone(), ttl);
+        }
+        answer
+    }
+
+    /// Get the endpoint's raw answer about `token`
+    fn fetch(&self, token: &str) -> Result<Vec<u8>, AuthError> {
+        let (url, credentials, timeout) = match &self.source {
+            Source::Endpoint {
+                url,
+                credentials,
+                timeout,
+            } => (url, credentials, *timeout),
+            Source::Custom(fetcher) => return fetcher(token),
+        };
+        let headers = [
+            ("Authorization", credentials.clone()),
+            (
+                "Content-Type",
+                "application/x-www-form-urlencoded".to_string(),
+            ),
+        ];
+        let body = format!("token={}&token_type_hint=access_token", form_encode(token));
+        fetch::http("POST", url, &headers, body.as_bytes(), timeout).map_err(|e| {
+            log::warn!("Token introspection at {} failed: {}", url, e);
+            AuthError::Rejected(format!("Token introspection failed: {}", e))
+        })
+    }
+
+    /// Cache an answer, making room by dropping expired entries, then the one expiring soonest
+    fn remember(&self, key: Vec<u8>, answer: Answer, ttl: Duration) {
+        let mut cache = self.cache.lock().unwrap_or_else(|e| e.into_inner());
+        if cache.len() >= MAX_CACHE_ENTRIES {
+            let now = Instant::now();
+            cache.retain(|_, (_, until)| *until > now);
+        }
+        if cache.len() >= MAX_CACHE_ENTRIES {
+            let soonest = cache
+                .iter()
+                .min_by_key(|(_, (_, until))| *until)
+                .map(|(key, _)| key.clone());
+            if let Some(soonest) = soonest {
+                cache.remove(&soonest);
+            }
+        }
+        cache.insert(key, (answer, Instant::now() + ttl));
+    }
+
+    /// Claims from an introspection response, and how long the token lives
+    fn claims(&self, response: &[u8]) -> Result<(Claims, Option<Duration>), AuthError> {
+        let mut raw: Map<String, Value> = serde_json::from_slice(response)
+            .map_err(|e| AuthError::Rejected(format!("Invalid introspection response: {}", e)))?;
+        if raw.remove("active") != Some(Value::Bool(true)) {
+            return Err(AuthError::Rejected("Token is not active".into()));
+        }
+
+        let now = SystemTime::now()
+            .duration_since(UNIX_EPOCH)
+            .unwrap_or_default()
+            .as_secs();
+        let exp = raw.get("exp").and_then(Value::as_u64);
+        if exp.is_some_and(|exp| exp <= now) {
+            return Err(AuthError::Rejected("Token has expired".into()));
+        }
+        raw.entry("exp").or_insert(0.into());
+
+        if let Some(issuer) = &self.issuer {
+            if raw.get("iss").and_then(Value::as_str) != Some(issuer.as_str()) {
+                return Err(AuthError::Rejected("Token has the wrong issuer".into()));
+            }
+        }
+        if let Some(audience) = &self.audience {
+            let matches = match raw.get("aud") {
+                Some(Value::String(aud)) => aud == audience,
+                Some(Value::Array(auds)) => auds.iter().any(|aud| aud.as_str() == Some(audience)),
+                _ => false,
+            };
+            if !matches {
+                return Err(AuthError::Rejected("Token has the wrong audience".into()));
+            }
+        }
+
+        let claims = self.mapping.claims(raw)?;
+        Ok((claims, exp.map(|exp| Duration::from_secs(exp - now))))
+    }
+}
+
+impl Authenticator for Introspection {
+    fn authenticate(&self, request: &AuthRequest<'_>) -> Result<Option<Claims>, AuthError> {
+        match request.bearer_token()? {
+            Some(token) => self.introspect(token).map(Some),
+            None => Ok(None),
+        }
+    }
+
+    fn uses_bearer_tokens(&self) -> bool {
+        true
+    }
+}
+
+/// Percent-encode for `application/x-www-form-urlencoded`
+fn form_encode(value: &str) -> String {
+    value
+        .bytes()
+        .map(|b| match b {
+            b'A'..=b'Z' | b'a'..=b'z' | b'0'..=b'9' | b'-' | b'.' | b'_' | b'*' => {
+                (b as char).to_string()
+            }
+            b' ' => "+".to_string(),
+            _ => format!("%{:02X}", b),
+        })
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::auth::authenticator::test_request;
+    use std::io::{BufRead, BufReader, Read, Write};
+    use std::net::TcpListener;
+    use std::sync::atomic::{AtomicUsize, Ordering};
+
+    #[test]
+    fn test_introspection_response() {
+        let calls = Arc::new(AtomicUsize::new(0));
+        let counter = calls.clone();
+        let introspection = Introspection::with_fetcher(move |token| {
+            counter.fetch_add(1, Ordering::SeqCst);
+            let response = match token {
+                "good" => r#"{"active":true,"sub":"alice","scope":"read write","client_id":"cli"}"#,
+                "expired" => r#"{"active":true,"sub":"bob","exp":1}"#,
+                "garbage" => "not json",
+                _ => r#"{"active":false}"#,
+            };
+            Ok(response.as_bytes().to_vec())
+        });
+
+        let claims = introspection.introspect("good").unwrap();
+        assert_eq!(claims.user_id(), "alice");
+        assert!(claims.has_scope("write"));
+        assert_eq!(claims.claim("client_id"), Some(&Value::from("cli")));
+        assert!(claims.claim("active").is_none());
+
+        // Served from the cache
+        introspection.introspect("good").unwrap();
+        assert_eq!(calls.load(Ordering::SeqCst), 1);
+
+        for (token, error) in [
+            ("revoked", "Token is not active"),
+            ("expired", "Token has expired"),
+            ("garbage", "Invalid introspection response"),
+        ] {
+            let message = introspection.introspect(token).unwrap_err().to_string();
+            assert!(message.contains(error), "{}: {}", token, message);
+        }
+
+        // Refusals are cached too
+        introspection.introspect("revoked").unwrap_err();
+        assert_eq!(calls.load(Ordering::SeqCst), 4);
+
+        let mapped =
+            Introspection::with_fetcher(|_| Ok(br#"{"active":true,"username":"carol"}"#.to_vec()))
+                .with_claim_mapping(ClaimMapping::new().user("username"))
+                .with_cache_ttl(Duration::ZERO);
+        assert_eq!(mapped.introspect("any").unwrap().user_id(), "carol");
+    }
+
+    #[test]
+    fn test_introspection_authenticates_bearer_tokens() {
+        let introspection = Introspection::with_fetcher(|token| match token {
+            "good" => Ok(br#"{"active":true,"sub":"alice"}"#.to_vec()),
+            _ => Ok(br#"{"active":false}"#.to_vec()),
+        });
+
+        let headers = [("Authorization".to_string(), "Bearer good".to_string())];
+        let claims = introspection.authenticate(&test_request(&headers));
+        assert_eq!(claims.unwrap().unwrap().user_id(), "alice");
+
+        let headers = [("Authorization".to_string(), "Bearer bad".to_string())];
+        assert!(introspection.authenticate(&test_request(&headers)).is_err());
+        assert!(matches!(
+            introspection.authenticate(&test_request(&[])),
+            Ok(None)
+        ));
+    }
+
+    #[test]
+    fn test_introspection_issuer_and_audience() {
+        let introspection = Introspection::with_fetcher(|token| {
+            let response = match token {
+                "ours" => r#"{"active":true,"sub":"a","iss":"https://idp","aud":["x","mcp"]}"#,
+                "other-aud" => r#"{"active":true,"sub":"a","iss":"https://idp","aud":"x"}"#,
+                "other-iss" => r#"{"active":true,"sub":"a","iss":"https://evil","aud":"mcp"}"#,
+                _ => r#"{"active":true,"sub":"a"}"#,
+            };
+            Ok(response.as_bytes().to_vec())
+        })
+        .with_issuer("https://idp")
+        .with_audience("mcp");
+
+        assert_eq!(introspection.introspect("ours").unwrap().user_id(), "a");
+        for (token, error) in [
+            ("other-aud", "wrong audience"),
+            ("other-iss", "wrong issuer"),
+            ("bare", "wrong issuer"),
+        ] {
+            let message = introspection.introspect(token).unwrap_err().to_string();
+            assert!(message.contains(error), "{}: {}", token, message);
+        }
+    }
+
+    #[test]
+    fn test_introspection_cache_is_bounded() {
+        let introspection = Introspection::with_fetcher(|_| Ok(br#"{"active":false}"#.to_vec()));
+        for i in 0..MAX_CACHE_ENTRIES + 100 {
+            introspection.introspect(&i.to_string()).unwrap_err();
+        }
+        let cached = introspection.cache.lock().unwrap().len();
+        assert_eq!(cached, MAX_CACHE_ENTRIES);
+    }
+
+    #[test]
+    fn test_introspection_endpoint() {
+        let listener = TcpListener::bind("127.0.0.1:0").unwrap();
+        let addr = listener.local_addr().unwrap();
+        let stub = std::thread::spawn(move || {
+            let (stream, _) = listener.accept().unwrap();
+            let mut reader = BufReader::new(stream);
+            let mut head = Vec::new();
+            let mut line = String::new();
+            while line != "\r\n" {
+                line.clear();
+                reader.read_line(&mut line).unwrap();
+                head.push(line.clone());
+            }
+            let length: usize = head
+                .iter()
+                .find_map(|h| h.strip_prefix("Content-Length: "))
+                .unwrap()
+                .trim()
+                .parse()
+                .unwrap();
+            let mut body = vec![0; length];
+            reader.read_exact(&mut body).unwrap();
+
+            let body = String::from_utf8(body).unwrap();
+            let active = body == "token=a%2Fb+c&token_type_hint=access_token";
+            write!(
+                reader.get_mut(),
+                "HTTP/1.0 200 OK\r\n\r\n{{\"active\":{},\"sub\":\"svc\"}}",
+                active
+            )
+            .unwrap();
+            head
+        });
+
+        let url = format!("http://{}/introspect", addr);
+        let introspection = Introspection::new(url, "mcp", "s3cret");
+        let claims = introspection.introspect("a/b c").unwrap();
+        assert_eq!(claims.user_id(), "svc");
+
+        let head = stub.join().unwrap();
+        assert_eq!(head[0], "POST /introspect HTTP/1.0\r\n");
+        let credentials = format!("Authorization: Basic {}\r\n", STANDARD.encode("mcp:s3cret"));
+        assert!(head.contains(&credentials));
+
+        // Unreachable endpoint
+        let introspection = Introspection::new(format!("http://{}/introspect", addr), "mcp", "x");
+        assert!(introspection.introspect("token").is_err());
+    }
+
+    #[test]
+    fn test_form_encode() {
+        assert_eq!(form_encode("a-b_c.d*"), "a-b_c.d*");
+        assert_eq!(form_encode("a b/c=d&é"), "a+b%2Fc%3Dd%26%C3%A9");
+    }
+}
@@ -0,0 +1,515 @@
+//! JWKS Key Discovery
+//!
+//! Loads verification keys from a JSON Web Key Set and keeps them fresh, so
+//! `JwtValidator` can follow key rotation at your identity provider.
+
+use super::fetch;
+use super::jwt::JwtError;
+use jsonwebtoken::jwk::{AlgorithmParameters, EllipticCurve, Jwk, PublicKeyUse};
+use jsonwebtoken::{Algorithm, DecodingKey};
+use serde::Deserialize;
+use serde_json::Value;
+use std::net::IpAddr;
+use std::path::PathBuf;
+use std::str::FromStr;
+use std::sync::{Arc, Mutex};
+use std::time::{Duration, Instant};
+
+/// Fetches the JWKS document at a URL and returns the response body
+pub type JwksFetcher = dyn Fn(&str) -> Result<Vec<u8>, JwtError> + Send + Sync;
+
+/// How often keys are reloaded even when every `kid` is known
+const DEFAULT_REFRESH_INTERVAL: Duration = Duration::from_secs(60 * 60);
+
+/// Minimum time between reloads, so unknown `kid`s can't hammer the IdP
+const DEFAULT_REFRESH_COOLDOWN: Duration = Duration::from_secs(30);
+
+enum Source {
+    /// Parsed once, never reloaded
+    Static,
+    File(PathBuf),
+    Url(String, Arc<JwksFetcher>),
+}
+
+/// A usable verification key from the set
+#[derive(Clone)]
+struct Key {
+    kid: Option<String>,
+    algorithms: Vec<Algorithm>,
+    key: DecodingKey,
+}
+
+#[derive(Default)]
+struct Cache {
+    keys: Vec<Key>,
+    /// Last successful load
+    loaded_at: Option<Instant>,
+    /// Last load attempt, successful or not
+    attempted_at: Option<Instant>,
+}
+
+/// A JSON Web Key Set with caching and refresh
+///
+/// Keys are chosen by the token's `kid` and `alg`. File and URL sets are
+/// reloaded every refresh interval, and once more when a token names a `kid`
+/// the cached set doesn't have (at most once per cooldown). If a reload
+/// fails, the previous keys stay in use.
+///
+/// # Example
+/// ```ignore
+/// let jwks = Jwks::from_url_with("https://idp.example.com/.well-known/jwks.json", |url| {
+///     https_get(url).map_err(|e| JwtError::Jwks(e.to_string()))
+/// })
+/// .with_refresh_interval(Duration::from_secs(600));
+/// let validator = JwtValidator::jwks(jwks).with_issuer("https://idp.example.com");
+/// ```
+pub struct Jwks {
+    source: Source,
+    refresh_interval: Duration,
+    refresh_cooldown: Duration,
+    cache: Mutex<Cache>,
+    /// Held while loading, so callers with no keys yet can wait for a load in progress
+    loading: Mutex<()>,
+}
+
+impl Jwks {
+    /// Use a fixed key set from a JWKS JSON document
+    pub fn from_bytes(json: &[u8]) -> Result<Self, JwtError> {
+        let jwks = Self::new(Source::Static);
+        jwks.lock().keys = parse(json)?;
+        Ok(jwks)
+    }
+
+    /// Load the key set from a file, re-reading it on refresh
+    pub fn from_file(path: impl Into<PathBuf>) -> Result<Self, JwtError> {
+        let jwks = Self::new(Source::File(path.into()));
+        jwks.refresh()?;
+        Ok(jwks)
+    }
+
+    /// Fetch the key set from a loopback `http://` URL on first use
+    ///
+    /// The built-in fetcher speaks plain HTTP, where anyone on the network
+    /// path could swap in their own keys and mint valid tokens. It only
+    /// reaches loopback hosts, such as a local sidecar. For anything else,
+    /// supply a TLS-capable fetcher with `from_url_with`.
+    pub fn from_url(url: impl Into<String>) -> Self {
+        Self::from_url_with(url, fetch_http)
+    }
+
+    /// Fetch the key set from a URL with a custom fetcher
+    pub fn from_url_with<F>(url: impl Into<String>, fetcher: F) -> Self
+    where
+        F: Fn(&str) -> Result<Vec<u8>, JwtError> + Send + Sync + 'static,
+    {
+        Self::new(Source::Url(url.into(), Arc::new(fetcher)))
+    }
+
+    /// Reload keys this often (default: one hour)
+    pub fn with_refresh_interval(mut self, interval: Duration) -> Self {
+        self.refresh_interval = interval;
+        self
+    }
+
+    /// Wait at least this long between reloads (default: 30 seconds)
+    pub fn with_refresh_cooldown(mut self, cooldown: Duration) -> Self {
+        self.refresh_cooldown = cooldown;
+        self
+    }
+
+    /// Reload the key set now
+    ///
+    /// Call this at startup to fail fast when a URL set is unreachable.
+    pub fn refresh(&self) -> Result<(), JwtError> {
+        self.lock().attempted_at = Some(Instant::now());
+        self.reload()
+    }
+
+    /// Keys that may verify a token with this `kid` and `alg`
+    ///
+    /// The cache isn't locked while keys load, so other tokens keep
+    /// validating against the cached set meanwhile.
+    pub(crate) fn candidates(
+        &self,
+        kid: Option<&str>,
+        alg: Algorithm,
+    ) -> Result<Vec<DecodingKey>, JwtError> {
+        let (stale, reload) = {
+            let mut cache = self.lock();
+            let stale = cache
+                .loaded_at
+                .is_none_or(|at| at.elapsed() >= self.refresh_interval);
+            (stale, stale && self.claim_reload(&mut cache))
+        };
+        if reload {
+            if let Err(e) = self.reload() {
+                if self.lock().loaded_at.is_none() {
+                    return Err(e);
+                }
+                log::warn!("JWKS refresh failed, keeping cached keys: {}", e);
+            }
+        } else if stale && self.lock().loaded_at.is_none() {
+            // Another caller is loading the first set; wait for it
+            drop(self.loading.lock().unwrap_or_else(|e| e.into_inner()));
+        }
+
+        let mut keys = matching(&self.lock().keys, kid, alg);
+        if keys.is_empty() && kid.is_some() && self.claim_reload(&mut self.lock()) {
+            log::info!("Unknown kid {:?}, refreshing JWKS", kid);
+            match self.reload() {
+                Ok(()) => keys = matching(&self.lock().keys, kid, alg),
+                Err(e) => log::warn!("JWKS refresh failed: {}", e),
+            }
+        }
+
+        if keys.is_empty() {
+            return Err(JwtError::UnknownKey(kid.unwrap_or("none").to_string()));
+        }
+        Ok(keys)
+    }
+
+    fn new(source: Source) -> Self {
+        Self {
+            source,
+            refresh_interval: DEFAULT_REFRESH_INTERVAL,
+            refresh_cooldown: DEFAULT_REFRESH_COOLDOWN,
+            cache: Mutex::new(Cache::default()),
+            loading: Mutex::new(()),
+        }
+    }
+
+    /// True if a reload may start now, recording the attempt so concurrent
+    /// callers and the cooldown see it
+    fn claim_reload(&self, cache: &mut Cache) -> bool {
+        let allowed = !matches!(self.source, Source::Static)
+            && cache
+                .attempted_at
+                .is_none_or(|at| at.elapsed() >= self.refresh_cooldown);
+        if allowed {
+            cache.attempted_at = Some(Instant::now());
+        }
+        allowed
+    }
+
+    /// Load the set from its source, then swap it into the cache
+    fn reload(&self) -> Result<(), JwtError> {
+        let _loading = self.loading.lock().unwrap_or_else(|e| e.into_inner());
+        let loaded = match &self.source {
+            Source::Static => return Ok(()),
+            Source::File(path) => std::fs::read(path)
+                .map_err(|e| JwtError::Jwks(format!("{}: {}", path.display(), e))),
+            Source::Url(url, fetcher) => fetcher(url),
+        };
+        let keys = parse(&loaded?)?;
+
+        let mut cache = self.lock();
+        cache.keys = keys;
+        cache.loaded_at = Some(Instant::now());
+        log::debug!("Loaded {} JWKS keys", cache.keys.len());
+        Ok(())
+    }
+
+    fn lock(&self) -> std::sync::MutexGuard<'_, Cache> {
+        // The cache is replaced wholesale, so a poisoned one is still consistent
+        self.cache.lock().unwrap_or_else(|e| e.into_inner())
+    }
+}
+
+fn matching(keys: &[Key], kid: Option<&str>, alg: Algorithm) -> Vec<DecodingKey> {
+    keys.iter()
+        .filter(|key| kid.is_none() || key.kid.as_deref() == kid)
+        .filter(|key| key.algorithms.contains(&alg))
+        .map(|key| key.key.clone())
+        .collect()
+}
+
+/// Parse a JWKS document, skipping keys we can't verify with
+fn parse(json: &[u8]) -> Result<Vec<Key>, JwtError> {
+    #[derive(Deserialize)]
+    struct Document {
+        keys: Vec<Value>,
+    }
+
+    let document: Document = serde_json::from_slice(json)
+        .map_err(|e| JwtError::Jwks(format!("Invalid JWKS document: {}", e)))?;
+
+    let keys: Vec<Key> = document
+        .keys
+        .into_iter()
+        .filter_map(|value| {
+            let jwk: Jwk = serde_json::from_value(value)
+                .inspect_err(|e| log::debug!("Skipping unsupported JWK: {}", e))
+                .ok()?;
+            to_key(&jwk)
+        })
+        .collect();
+
+    if keys.is_empty() {
+        return Err(JwtError::Jwks("JWKS has no usable signing keys".into()));
+    }
+    Ok(keys)
+}
+
+/// Parse a single JWK into a key and the algorithms it may verify
+pub(super) fn parse_jwk(json: &[u8]) -> Result<(DecodingKey, Vec<Algorithm>), JwtError> {
+    let jwk: Jwk = serde_json::from_slice(json).map_err(|e| JwtError::InvalidKey(e.to_string()))?;
+    let key =
+        to_key(&jwk).ok_or_else(|| JwtError::InvalidKey("JWK can't verify signatures".into()))?;
+    Ok((key.key, key.algorithms))
+}
+
+fn to_key(jwk: &Jwk) -> Option<Key> {
+    if jwk.common.public_key_use == Some(PublicKeyUse::Encryption) {
+        return None;
+    }
+
+    // A declared `alg` pins the key to it; otherwise allow what the key type can verify
+    let algorithms = match jwk.common.key_algorithm {
+        Some(alg) => vec![Algorithm::from_str(&alg.to_string()).ok()?],
+        None => match &jwk.algorithm {
+            AlgorithmParameters::RSA(_) => vec![
+                Algorithm::RS256,
+                Algorithm::RS384,
+                Algorithm::RS512,
+                Algorithm::PS256,
+                Algorithm::PS384,
+                Algorithm::PS512,
+            ],
+            AlgorithmParameters::EllipticCurve(params) => match params.curve {
+                EllipticCurve::P256 => vec![Algorithm::ES256],
+                EllipticCurve::P384 => vec![Algorithm::ES384],
+                _ => return None,
+            },
+            AlgorithmParameters::OctetKeyPair(params) => match params.curve {
+                EllipticCurve::Ed25519 => vec![Algorithm::EdDSA],
+                _ => return None,
+            },
+            AlgorithmParameters::OctetKey(_) => {
+                vec![Algorithm::HS256, Algorithm::HS384, Algorithm::HS512]
+            }
+        },
+    };
+
+    let key = DecodingKey::from_jwk(jwk)
+        .inspect_err(|e| log::debug!("Skipping invalid JWK: {}", e))
+        .ok()?;
+    Some(Key {
+        kid: jwk.common.key_id.clone(),
+        algorithms,
+        key,
+    })
+}
+
+/// Minimal HTTP/1.0 GET for loopback `http://` JWKS URLs
+fn fetch_http(url: &str) -> Result<Vec<u8>, JwtError> {
+    if !is_loopback_url(url) {
+        return Err(JwtError::Jwks(format!(
+            "{}: only loopback http:// is built in, use Jwks::from_url_with",
+            url
+        )));
+    }
+    fetch::http("GET", url, &[], &[], fetch::DEFAULT_TIMEOUT)
+        .map_err(|e| JwtError::Jwks(format!("{}: {}", url, e)))
+}
+
+/// True for `http://` URLs whose host is `localhost` or a loopback address
+fn is_loopback_url(url: &str) -> bool {
+    let Some(rest) = url.strip_prefix("http://") else {
+        return false;
+    };
+    let authority = rest.split(['/', '?', '#']).next().unwrap_or_default();
+    if authority.contains('@') {
+        return false;
+    }
+    let host = match authority.strip_prefix('[') {
+        Some(bracketed) => bracketed.split(']').next().unwrap_or_default(),
+        None => authority.split(':').next().unwrap_or_default(),
+    };
+    host.eq_ignore_ascii_case("localhost")
+        || host.parse::<IpAddr>().is_ok_and(|ip| ip.is_loopback())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_parse_skips_unusable_keys() {
+        let json = br#"{"keys": [
+            {"kty": "oct", "kid": "hmac", "k": "c2VjcmV0"},
+            {"kty": "RSA", "use": "enc", "kid": "enc", "n": "AQAB", "e": "AQAB"},
+            {"kty": "EC", "kid": "k1", "crv": "secp256k1", "x": "AA", "y": "AA"},
+            {"kty": "unknown", "kid": "x"}
+        ]}"#;
+        let keys = parse(json).unwrap();
+        assert_eq!(keys.len(), 1);
+        assert_eq!(keys[0].kid.as_deref(), Some("hmac"));
+        assert_eq!(
+            keys[0].algorithms,
+            vec![Algorithm::HS256, Algorithm::HS384, Algorithm::HS512]
+        );
+
+        assert!(parse(br#"{"keys": []}"#).is_err());
+        assert!(parse(b"not json").is_err());
+    }
+
+    #[test]
+    fn test_candidates_by_kid_and_alg() {
+        let json = br#"{"keys": [
+            {"kty": "oct", "kid": "a", "alg": "HS256", "k": "c2VjcmV0"},
+            {"kty": "oct", "kid": "b", "k": "b3RoZXI"}
+        ]}"#;
+        let jwks = Jwks::from_bytes(json).unwrap();
+
+        assert_eq!(
+            jwks.candidates(Some("a"), Algorithm::HS256).unwrap().len(),
+            1
+        );
+        assert_eq!(jwks.candidates(None, Algorithm::HS256).unwrap().len(), 2);
+        assert_eq!(jwks.candidates(None, Algorithm::HS512).unwrap().len(), 1);
+        // `a` is pinned to HS256
+        assert!(matches!(
+            jwks.candidates(Some("a"), Algorithm::HS384),
+            Err(JwtError::UnknownKey(_))
+        ));
+        assert!(jwks.candidates(Some("c"), Algorithm::HS256).is_err());
+    }
+
+    #[test]
+    fn test_refresh_on_unknown_kid() {
+        let served = Arc::new(Mutex::new(
+            r#"{"keys": [{"kty": "oct", "kid": "old", "k": "AA"}]}"#,
+        ));
+        let fetches = Arc::new(Mutex::new(0));
+        let (body, count) = (served.clone(), fetches.clone());
+        let jwks = Jwks::from_url_with("http://idp.test/jwks", move |url| {
+            assert_eq!(url, "http://idp.test/jwks");
+            *count.lock().unwrap() += 1;
+            Ok(body.lock().unwrap().as_bytes().to_vec())
+        })
+        .with_refresh_cooldown(Duration::ZERO);
+
+        assert_eq!(
+            jwks.candidates(Some("old"), Algorithm::HS256)
+                .unwrap()
+                .len(),
+            1
+        );
+        assert_eq!(*fetches.lock().unwrap(), 1);
+
+        // Rotation: the unknown kid triggers exactly one reload
+        *served.lock().unwrap() = r#"{"keys": [{"kty": "oct", "kid": "new", "k": "AA"}]}"#;
+        assert_eq!(
+            jwks.candidates(Some("new"), Algorithm::HS256)
+                .unwrap()
+                .len(),
+            1
+        );
+        assert_eq!(*fetches.lock().unwrap(), 2);
+        assert!(jwks.candidates(Some("gone"), Algorithm::HS256).is_err());
+        assert_eq!(*fetches.lock().unwrap(), 3);
+
+        // The cooldown limits reloads
+        let jwks = jwks.with_refresh_cooldown(Duration::from_secs(60));
+        assert!(jwks.candidates(Some("gone"), Algorithm::HS256).is_err());
+        assert_eq!(*fetches.lock().unwrap(), 3);
+    }
+
+    #[test]
+    fn test_cached_keys_usable_during_reload() {
+        let (started, release) = (
+            Arc::new(std::sync::Barrier::new(2)),
+            Arc::new(std::sync::Barrier::new(2)),
+        );
+        let (on_fetch, on_release) = (started.clone(), release.clone());
+        let fetches = Arc::new(Mutex::new(0));
+        let count = fetches.clone();
+        let jwks = Arc::new(
+            Jwks::from_url_with("http://idp.test/jwks", move |_| {
+                let fetch = {
+                    let mut count = count.lock().unwrap();
+                    *count += 1;
+                    *count
+                };
+                if fetch > 1 {
+                    // The reload hangs until the test lets it go
+                    on_fetch.wait();
+                    on_release.wait();
+                }
+                Ok(br#"{"keys": [{"kty": "oct", "kid": "a", "k": "AA"}]}"#.to_vec())
+            })
+            .with_refresh_cooldown(Duration::ZERO),
+        );
+        jwks.refresh().unwrap();
+
+        let reloading = jwks.clone();
+        let reload = std::thread::spawn(move || {
+            assert!(reloading.candidates(Some("b"), Algorithm::HS256).is_err());
+        });
+        started.wait();
+
+        // A known kid is served from the cache while the reload is stuck
+        assert_eq!(
+            jwks.candidates(Some("a"), Algorithm::HS256).unwrap().len(),
+            1
+        );
+        release.wait();
+        reload.join().unwrap();
+        assert_eq!(*fetches.lock().unwrap(), 2);
+    }
+
+    #[test]
+    fn test_fetch_http() {
+        use std::io::{BufRead, Write};
+        use std::net::TcpListener;
+
+        let listener = TcpListener::bind("127.0.0.1:0").unwrap();
+        let addr = listener.local_addr().unwrap();
+        let handle = std::thread::spawn(move || {
+            for response in ["200 OK\r\n\r\n{\"keys\":[]}", "404 Not Found\r\n\r\n"] {
+                let (stream, _) = listener.accept().unwrap();
+                let mut reader = std::io::BufReader::new(stream);
+                let mut line = String::new();
+                reader.read_line(&mut line).unwrap();
+                assert_eq!(line, "GET /.well-known/jwks.json HTTP/1.0\r\n");
+                while line != "\r\n" {
+                    line.clear();
+                    reader.read_line(&mut line).unwrap();
+                }
+                write!(reader.get_mut(), "HTTP/1.0 {}", response).unwrap();
+            }
+        });
+
+        let url = format!("http://{}/.well-known/jwks.json", addr);
+        assert_eq!(fetch_http(&url).unwrap(), br#"{"keys":[]}"#);
+        assert!(fetch_http(&url).is_err());
+        assert!(fetch_http("https://idp.test/jwks").is_err());
+        handle.join().unwrap();
+    }
+
+    #[test]
+    fn test_built_in_fetcher_stays_on_loopback() {
+        for url in [
+            "http://127.0.0.1:8080/jwks",
+            "http://localhost/jwks",
+            "http://[::1]:9000/jwks",
+        ] {
+            assert!(is_loopback_url(url), "{}", url);
+        }
+        for url in [
+            "http://idp.internal/jwks",
+            "http://10.0.0.5/jwks",
+            "http://127.0.0.1.evil.test/jwks",
+            "http://127.0.0.1@idp.evil.test/jwks",
+            "https://127.0.0.1/jwks",
+        ] {
+            assert!(!is_loopback_url(url), "{}", url);
+        }
+
+        // Refused before any connection is attempted
+        let error = Jwks::from_url("http://idp.internal/jwks")
+            .refresh()
+            .unwrap_err();
+        assert!(error.to_string().contains("loopback"), "{}", error);
+    }
+}
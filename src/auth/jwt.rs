@@ -3,8 +3,13 @@
 //! Validates tokens and extracts claims. Does NOT issue tokens -
 //! that's the job of your OAuth provider (Auth0, Cognito, etc).
 
-use jsonwebtoken::{Algorithm, DecodingKey, TokenData, Validation, decode};
-use serde::{Deserialize, Serialize};
+use super::jwks::{Jwks, parse_jwk};
+use base64::{Engine, engine::general_purpose::STANDARD};
+use jsonwebtoken::errors::ErrorKind;
+use jsonwebtoken::{Algorithm, DecodingKey, Validation, decode, decode_header};
+use serde::{Deserialize, Deserializer, Serialize};
+use serde_json::{Map, Value};
+use std::collections::HashMap;
 use thiserror::Error;
 
 #[derive(Error, Debug)]
@@ -26,12 +31,30 @@ pub enum JwtError {
 
     #[error("Invalid audience")]
     InvalidAudience,
+
+    #[error("No signing key matches kid {0}")]
+    UnknownKey(String),
+
+    #[error("JWKS unavailable: {0}")]
+    Jwks(String),
+
+    #[error("Invalid key: {0}")]
+    InvalidKey(String),
+
+    #[error("Invalid algorithm configuration: {0}")]
+    Algorithms(String),
+
+    #[error("Missing claim: {0}")]
+    MissingClaim(String),
+
+    #[error("Invalid claims: {0}")]
+    InvalidClaims(String),
 }
 
 /// Standard JWT claims plus custom fields for multi-tenancy
-#[derive(Debug, Clone, Serialize, Deserialize)]
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
 pub struct Claims {
-    /// Subject (user ID)
+    /// Subject (user ID), or the claim mapped with `ClaimMapping::user`
     pub sub: String,
 
     /// Expiration time (Unix timestamp)
@@ -45,17 +68,25 @@ pub struct Claims {
     #[serde(default)]
     pub iss: Option<String>,
 
-    /// Audience
-    #[serde(default)]
-    pub aud: Option<String>,
+    /// Audience - a single string or an array in the token
+    #[serde(
+        default,
+        deserialize_with = "one_or_many",
+        skip_serializing_if = "Vec::is_empty"
+    )]
+    pub aud: Vec<String>,
 
-    /// Tenant ID for multi-tenancy (custom claim)
+    /// Tenant ID for multi-tenancy (custom claim, or mapped with `ClaimMapping::tenant`)
     #[serde(default)]
     pub tenant_id: Option<String>,
 
-    /// Scopes/permissions (custom claim)
-    #[serde(default)]
+    /// Space-separated scopes/permissions (custom claim, or mapped with `ClaimMapping::scopes`)
+    #[serde(default, deserialize_with = "space_separated")]
     pub scope: Option<String>,
+
+    /// Every other claim in the token, e.g. namespaced or IdP-specific ones
+    #[serde(flatten)]
+    pub extra: HashMap<String, Value>,
 }
 
 impl Claims {
@@ -71,17 +102,217 @@ impl Claims {
 
     /// Check if a scope is present
     pub fn has_scope(&self, scope: &str) -> bool {
-        self.scope
-            .as_ref()
-            .map(|s| s.split_whitespace().any(|s| s == scope))
-            .unwrap_or(false)
+        self.scopes().any(|s| s == scope)
+    }
+
+    /// All scopes, in token order
+    pub fn scopes(&self) -> impl Iterator<Item = &str> {
+        self.scope.iter().flat_map(|s| s.split_whitespace())
     }
+
+    /// An extra claim by name, or by JSON Pointer (e.g. `/realm_access/roles`)
+    ///
+    /// Claims with a field of their own (`sub`, `aud`, ...) aren't in `extra`.
+    pub fn claim(&self, name: &str) -> Option<&Value> {
+        lookup(name, |key| self.extra.get(key))
+    }
+}
+
+/// Which claims give the user, tenant and scopes
+///
+/// Names are claim names, including namespaced ones like
+/// `https://example.com/org_id`, or JSON Pointers into nested claims such as
+/// `/realm_access/roles`. Mapped values replace `sub`, `tenant_id` and
+/// `scope` in the resulting `Claims`.
+///
+/// # Example
+/// ```ignore
+/// let mapping = ClaimMapping::new()
+///     .user("oid")
+///     .tenant("https://example.com/org_id")
+///     .scopes(&["scp", "roles"]);
+/// let validator = JwtValidator::jwks(jwks).with_claim_mapping(mapping);
+/// ```
+#[derive(Debug, Clone, Default)]
+pub struct ClaimMapping {
+    user: Option<String>,
+    tenant: Option<String>,
+    scopes: Vec<String>,
 }
 
+impl ClaimMapping {
+    /// The default mapping: `sub`, `tenant_id` and `scope`
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Read the user ID from `claim` instead of `sub`
+    pub fn user(mut self, claim: &str) -> Self {
+        self.user = Some(claim.to_string());
+        self
+    }
+
+    /// Read the tenant ID from `claim` instead of `tenant_id`
+    pub fn tenant(mut self, claim: &str) -> Self {
+        self.tenant = Some(claim.to_string());
+        self
+    }
+
+    /// Collect scopes from these claims instead of `scope`
+    ///
+    /// Each claim may be a space-separated string or an array of strings.
+    pub fn scopes(mut self, claims: &[&str]) -> Self {
+        self.scopes = claims.iter().map(|c| c.to_string()).collect();
+        self
+    }
+
+    /// Build `Claims` from a token payload
+    pub(crate) fn claims(&self, mut raw: Map<String, Value>) -> Result<Claims, JwtError> {
+        if let Some(name) = &self.user {
+            let user = lookup(name, |key| raw.get(key))
+                .and_then(text)
+                .ok_or_else(|| JwtError::MissingClaim(name.clone()))?;
+            raw.insert("sub".into(), user.into());
+        }
+
+        if let Some(name) = &self.tenant {
+            match lookup(name, |key| raw.get(key)).and_then(text) {
+                Some(tenant) => raw.insert("tenant_id".into(), tenant.into()),
+                None => raw.remove("tenant_id"),
+            };
+        }
+
+        if !self.scopes.is_empty() {
+            let scopes: Vec<&str> = self
+                .scopes
+                .iter()
+                .filter_map(|name| lookup(name, |key| raw.get(key)))
+                .flat_map(words)
+                .collect();
+            let scope = (!scopes.is_empty()).then(|| scopes.join(" "));
+            match scope {
+                Some(scope) => raw.insert("scope".into(), scope.into()),
+                None => raw.remove("scope"),
+            };
+        }
+
+        serde_json::from_value(Value::Object(raw))
+            .map_err(|e| JwtError::InvalidClaims(e.to_string()))
+    }
+}
+
+/// Find a claim by name or JSON Pointer
+fn lookup<'a>(name: &str, get: impl Fn(&str) -> Option<&'a Value>) -> Option<&'a Value> {
+    let Some(path) = name.strip_prefix('/') else {
+        return get(name);
+    };
+    let (first, rest) = match path.split_once('/') {
+        Some((first, rest)) => (first, Some(rest)),
+        None => (path, None),
+    };
+    let value = get(&first.replace("~1", "/").replace("~0", "~"))?;
+    match rest {
+        Some(rest) => value.pointer(&format!("/{}", rest)),
+        None => Some(value),
+    }
+}
+
+/// A claim as text, if it is a string or number
+fn text(value: &Value) -> Option<String> {
+    match value {
+        Value::String(s) => Some(s.clone()),
+        Value::Number(n) => Some(n.to_string()),
+        _ => None,
+    }
+}
+
+/// Words of a space-separated string, or the strings in an array
+fn words(value: &Value) -> Vec<&str> {
+    match value {
+        Value::String(s) => s.split_whitespace().collect(),
+        Value::Array(items) => items.iter().filter_map(Value::as_str).collect(),
+        _ => Vec::new(),
+    }
+}
+
+#[derive(Deserialize)]
+#[serde(untagged)]
+enum OneOrMany {
+    One(String),
+    Many(Vec<String>),
+}
+
+/// `"a"` or `["a", "b"]` as a list
+fn one_or_many<'de, D: Deserializer<'de>>(deserializer: D) -> Result<Vec<String>, D::Error> {
+    Ok(match Option::<OneOrMany>::deserialize(deserializer)? {
+        Some(OneOrMany::One(one)) => vec![one],
+        Some(OneOrMany::Many(many)) => many,
+        None => Vec::new(),
+    })
+}
+
+/// `"a b"` or `["a", "b"]` as a space-separated string
+fn space_separated<'de, D: Deserializer<'de>>(deserializer: D) -> Result<Option<String>, D::Error> {
+    Ok(match Option::<OneOrMany>::deserialize(deserializer)? {
+        Some(OneOrMany::One(one)) => Some(one),
+        Some(OneOrMany::Many(many)) => Some(many.join(" ")),
+        None => None,
+    })
+}
+
+/// Key types; a validator never accepts algorithms across them
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum Family {
+    Hmac,
+    Rsa,
+    Ec,
+    Ed,
+}
+
+impl Family {
+    fn of(alg: Algorithm) -> Self {
+        match alg {
+            Algorithm::HS256 | Algorithm::HS384 | Algorithm::HS512 => Family::Hmac,
+            Algorithm::RS256
+            | Algorithm::RS384
+            | Algorithm::RS512
+            | Algorithm::PS256
+            | Algorithm::PS384
+            | Algorithm::PS512 => Family::Rsa,
+            Algorithm::ES256 | Algorithm::ES384 => Family::Ec,
+            Algorithm::EdDSA => Family::Ed,
+        }
+    }
+}
+
+/// Where verification keys come from
+enum Keys {
+    Single(DecodingKey, Family),
+    Jwks(Jwks),
+}
+
+/// Algorithms accepted by default from a JWKS - never HMAC
+const JWKS_ALGORITHMS: [Algorithm; 9] = [
+    Algorithm::RS256,
+    Algorithm::RS384,
+    Algorithm::RS512,
+    Algorithm::PS256,
+    Algorithm::PS384,
+    Algorithm::PS512,
+    Algorithm::ES256,
+    Algorithm::ES384,
+    Algorithm::EdDSA,
+];
+
 /// JWT Validator configuration
+///
+/// Each validator accepts a fixed set of algorithms, all matching its key
+/// type, so a token can't pick a weaker algorithm - e.g. HS256 signed with
+/// an RSA public key as the secret.
 pub struct JwtValidator {
-    decoding_key: DecodingKey,
+    keys: Keys,
     validation: Validation,
+    mapping: ClaimMapping,
 }
 
 impl JwtValidator {
@@ -89,37 +320,114 @@ impl JwtValidator {
     ///
     /// Use this for development/testing. In production, prefer RS256.
     pub fn hs256(secret: &[u8]) -> Self {
-        let mut validation = Validation::new(Algorithm::HS256);
-        validation.validate_exp = true;
-
-        Self {
-            decoding_key: DecodingKey::from_secret(secret),
-            validation,
-        }
+        Self::single(DecodingKey::from_secret(secret), &[Algorithm::HS256])
     }
 
     /// Create a validator for RS256 (asymmetric) tokens
     ///
     /// Use this in production with your OAuth provider's public key.
     pub fn rs256_pem(public_key_pem: &[u8]) -> Result<Self, JwtError> {
-        let mut validation = Validation::new(Algorithm::RS256);
-        validation.validate_exp = true;
-
-        Ok(Self {
-            decoding_key: DecodingKey::from_rsa_pem(public_key_pem)?,
-            validation,
-        })
+        let key = DecodingKey::from_rsa_pem(public_key_pem)?;
+        Ok(Self::single(key, &[Algorithm::RS256]))
     }
 
     /// Create a validator for RS256 using JWKS components (n, e)
     pub fn rs256_components(n: &str, e: &str) -> Result<Self, JwtError> {
+        let key = DecodingKey::from_rsa_components(n, e)?;
+        Ok(Self::single(key, &[Algorithm::RS256]))
+    }
+
+    /// Create a validator for PS256 (RSA-PSS) tokens from a PEM public key
+    pub fn ps256_pem(public_key_pem: &[u8]) -> Result<Self, JwtError> {
+        let key = DecodingKey::from_rsa_pem(public_key_pem)?;
+        Ok(Self::single(key, &[Algorithm::PS256]))
+    }
+
+    /// Create a validator for PS256 from a DER (SubjectPublicKeyInfo) public key
+    pub fn ps256_der(public_key_der: &[u8]) -> Result<Self, JwtError> {
+        Self::ps256_pem(&der_to_pem(public_key_der))
+    }
+
+    /// Create a validator for ES256 (ECDSA P-256) tokens from a PEM public key
+    pub fn es256_pem(public_key_pem: &[u8]) -> Result<Self, JwtError> {
+        let key = DecodingKey::from_ec_pem(public_key_pem)?;
+        Ok(Self::single(key, &[Algorithm::ES256]))
+    }
+
+    /// Create a validator for ES256 from a DER (SubjectPublicKeyInfo) public key
+    pub fn es256_der(public_key_der: &[u8]) -> Result<Self, JwtError> {
+        Self::es256_pem(&der_to_pem(public_key_der))
+    }
+
+    /// Create a validator for EdDSA (Ed25519) tokens from a PEM public key
+    pub fn eddsa_pem(public_key_pem: &[u8]) -> Result<Self, JwtError> {
+        let key = DecodingKey::from_ed_pem(public_key_pem)?;
+        Ok(Self::single(key, &[Algorithm::EdDSA]))
+    }
+
+    /// Create a validator for EdDSA from a DER (SubjectPublicKeyInfo) public key
+    pub fn eddsa_der(public_key_der: &[u8]) -> Result<Self, JwtError> {
+        Self::eddsa_pem(&der_to_pem(public_key_der))
+    }
+
+    /// Create a validator from a single JSON Web Key
+    ///
+    /// Accepts the key's `alg` if set, otherwise every algorithm its key
+    /// type can verify (e.g. RS256 through PS512 for an RSA key).
+    pub fn from_jwk(jwk_json: &[u8]) -> Result<Self, JwtError> {
+        let (key, algorithms) = parse_jwk(jwk_json)?;
+        Ok(Self::single(key, &algorithms))
+    }
+
+    /// Create a validator that picks keys from a JSON Web Key Set
+    ///
+    /// The key is chosen by the token's `kid` and `alg`, and the set is
+    /// refreshed as your identity provider rotates keys. Asymmetric
+    /// algorithms are accepted by default; HMAC keys in the set are only
+    /// used if you opt in with `with_algorithms`.
+    pub fn jwks(jwks: Jwks) -> Self {
         let mut validation = Validation::new(Algorithm::RS256);
         validation.validate_exp = true;
+        validation.algorithms = JWKS_ALGORITHMS.to_vec();
 
-        Ok(Self {
-            decoding_key: DecodingKey::from_rsa_components(n, e)?,
+        Self {
+            keys: Keys::Jwks(jwks),
             validation,
-        })
+            mapping: ClaimMapping::default(),
+        }
+    }
+
+    /// Replace the accepted algorithms
+    ///
+    /// Every algorithm must match the key type - e.g. RS256 and PS256 for an
+    /// RSA key. For a JWKS, HMAC can't be combined with asymmetric
+    /// algorithms, and each key still only verifies its own type.
+    pub fn with_algorithms(mut self, algorithms: &[Algorithm]) -> Result<Self, JwtError> {
+        let Some(&first) = algorithms.first() else {
+            return Err(JwtError::Algorithms("no algorithms given".into()));
+        };
+        let is_hmac = |alg: Algorithm| Family::of(alg) == Family::Hmac;
+
+        match &self.keys {
+            Keys::Single(_, family) => {
+                if let Some(alg) = algorithms.iter().find(|a| Family::of(**a) != *family) {
+                    return Err(JwtError::Algorithms(format!(
+                        "{:?} doesn't match the {:?} key",
+                        alg, family
+                    )));
+                }
+            }
+            // Each JWK only verifies its own type, but HMAC never mixes in
+            Keys::Jwks(_) => {
+                if algorithms.iter().any(|a| is_hmac(*a) != is_hmac(first)) {
+                    return Err(JwtError::Algorithms(
+                        "HMAC can't be combined with asymmetric algorithms".into(),
+                    ));
+                }
+            }
+        }
+        self.validation.algorithms = algorithms.to_vec();
+        Ok(self)
     }
 
     /// Require a specific issuer
@@ -134,6 +442,24 @@ impl JwtValidator {
         self
     }
 
+    fn single(key: DecodingKey, algorithms: &[Algorithm]) -> Self {
+        let mut validation = Validation::new(algorithms[0]);
+        validation.validate_exp = true;
+        validation.algorithms = algorithms.to_vec();
+
+        Self {
+            keys: Keys::Single(key, Family::of(algorithms[0])),
+            validation,
+            mapping: ClaimMapping::default(),
+        }
+    }
+
+    /// Choose which claims give the user, tenant and scopes
+    pub fn with_claim_mapping(mut self, mapping: ClaimMapping) -> Self {
+        self.mapping = mapping;
+        self
+    }
+
     /// Extract token from Authorization header
     pub fn extract_token(auth_header: &str) -> Result<&str, JwtError> {
         auth_header
@@ -143,8 +469,33 @@ impl JwtValidator {
 
     /// Validate a token and return claims
     pub fn validate(&self, token: &str) -> Result<Claims, JwtError> {
-        let token_data: TokenData<Claims> = decode(token, &self.decoding_key, &self.validation)?;
-        Ok(token_data.claims)
+        let raw = match &self.keys {
+            Keys::Single(key, _) => decode(token, key, &self.validation)?.claims,
+            Keys::Jwks(jwks) => self.decode_jwks(token, jwks)?,
+        };
+        self.mapping.claims(raw)
+    }
+
+    /// Verify against the JWKS key for the token's `kid` and `alg`
+    fn decode_jwks(&self, token: &str, jwks: &Jwks) -> Result<Map<String, Value>, JwtError> {
+        // Each candidate key is only tried with the algorithm it allows
+        let header = decode_header(token)?;
+        if !self.validation.algorithms.contains(&header.alg) {
+            return Err(jsonwebtoken::errors::Error::from(ErrorKind::InvalidAlgorithm).into());
+        }
+        let mut validation = self.validation.clone();
+        validation.algorithms = vec![header.alg];
+
+        let mut result = Err(JwtError::UnknownKey("none".into()));
+        for key in jwks.candidates(header.kid.as_deref(), header.alg)? {
+            result = decode(token, &key, &validation)
+                .map(|data| data.claims)
+                .map_err(JwtError::from);
+            if result.is_ok() {
+                break;
+            }
+        }
+        result
     }
 
     /// Validate from Authorization header value
@@ -154,6 +505,21 @@ impl JwtValidator {
     }
 }
 
+/// Wrap a DER (SubjectPublicKeyInfo) public key as PEM
+///
+/// `jsonwebtoken`'s own DER constructors expect raw key bytes, not the
+/// SubjectPublicKeyInfo that `openssl pkey -pubout -outform DER` writes.
+fn der_to_pem(der: &[u8]) -> Vec<u8> {
+    let encoded = STANDARD.encode(der);
+    let mut pem = String::from("-----BEGIN PUBLIC KEY-----\n");
+    for line in encoded.as_bytes().chunks(64) {
+        pem.push_str(&String::from_utf8_lossy(line));
+        pem.push('\n');
+    }
+    pem.push_str("-----END PUBLIC KEY-----\n");
+    pem.into_bytes()
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
@@ -180,11 +546,9 @@ mod tests {
                 .unwrap()
                 .as_secs())
                 + 3600, // 1 hour from now
-            iat: 0,
-            iss: None,
-            aud: None,
             tenant_id: Some("tenant-456".to_string()),
             scope: Some("read write".to_string()),
+            ..Default::default()
         };
 
         let token = create_test_token(&claims, secret);
@@ -205,11 +569,7 @@ mod tests {
         let claims = Claims {
             sub: "user-123".to_string(),
             exp: 1000, // Way in the past
-            iat: 0,
-            iss: None,
-            aud: None,
-            tenant_id: None,
-            scope: None,
+            ..Default::default()
         };
 
         let token = create_test_token(&claims, secret);
@@ -237,14 +597,271 @@ mod tests {
         let claims = Claims {
             sub: "user-123".to_string(),
             exp: 0,
-            iat: 0,
-            iss: None,
-            aud: None,
-            tenant_id: None, // No tenant_id
-            scope: None,
+            ..Default::default()
         };
 
         // Should fall back to user_id
         assert_eq!(claims.tenant_id(), "user-123");
     }
+
+    const RSA_KEY_1: &[u8] = include_bytes!("testdata/rsa1.key");
+    const RSA_KEY_2: &[u8] = include_bytes!("testdata/rsa2.key");
+    const RSA_JWK_1: &str = include_str!("testdata/rsa1.jwk.json");
+    const RSA_JWK_2: &str = include_str!("testdata/rsa2.jwk.json");
+
+    const RSA_PUB_PEM: &[u8] = include_bytes!("testdata/rsa1.pub.pem");
+    const RSA_PUB_DER: &[u8] = include_bytes!("testdata/rsa1.pub.der");
+    const EC_KEY: &[u8] = include_bytes!("testdata/ec.key");
+    const EC_PUB_PEM: &[u8] = include_bytes!("testdata/ec.pub.pem");
+    const EC_PUB_DER: &[u8] = include_bytes!("testdata/ec.pub.der");
+    const EC_JWK: &[u8] = include_bytes!("testdata/ec.jwk.json");
+    const ED_KEY: &[u8] = include_bytes!("testdata/ed25519.key");
+    const ED_PUB_PEM: &[u8] = include_bytes!("testdata/ed25519.pub.pem");
+    const ED_PUB_DER: &[u8] = include_bytes!("testdata/ed25519.pub.der");
+    const ED_JWK: &[u8] = include_bytes!("testdata/ed25519.jwk.json");
+
+    /// A valid token for `user-123`
+    fn sign(alg: Algorithm, kid: Option<&str>, key: &EncodingKey) -> String {
+        let claims = Claims {
+            sub: "user-123".to_string(),
+            exp: u64::MAX / 2,
+            ..Default::default()
+        };
+        let mut header = Header::new(alg);
+        header.kid = kid.map(String::from);
+        encode(&header, &claims, key).unwrap()
+    }
+
+    fn rs256_token(kid: &str, key: &[u8]) -> String {
+        let key = EncodingKey::from_rsa_pem(key).unwrap();
+        sign(Algorithm::RS256, Some(kid), &key)
+    }
+
+    #[test]
+    fn test_jwks_key_rotation() {
+        use std::sync::{Arc, Mutex};
+
+        let served = Arc::new(Mutex::new(format!(r#"{{"keys": [{}]}}"#, RSA_JWK_1)));
+        let body = served.clone();
+        let jwks = Jwks::from_url_with("http://idp.test/jwks", move |_| {
+            Ok(body.lock().unwrap().as_bytes().to_vec())
+        })
+        .with_refresh_cooldown(std::time::Duration::ZERO);
+        let validator = JwtValidator::jwks(jwks);
+
+        let token = rs256_token("key-1", RSA_KEY_1);
+        assert_eq!(validator.validate(&token).unwrap().user_id(), "user-123");
+
+        // A key signed with the wrong private key fails even with a known kid
+        let forged = rs256_token("key-1", RSA_KEY_2);
+        assert!(matches!(
+            validator.validate(&forged),
+            Err(JwtError::ValidationFailed(_))
+        ));
+
+        // The IdP rotates to key-2; the unknown kid triggers a refresh
+        *served.lock().unwrap() = format!(r#"{{"keys": [{}, {}]}}"#, RSA_JWK_1, RSA_JWK_2);
+        let rotated = rs256_token("key-2", RSA_KEY_2);
+        assert!(validator.validate(&rotated).is_ok());
+        assert!(matches!(
+            validator.validate(&rs256_token("key-3", RSA_KEY_2)),
+            Err(JwtError::UnknownKey(kid)) if kid == "key-3"
+        ));
+    }
+
+    #[test]
+    fn test_jwks_rejects_algorithm_mismatch() {
+        let jwks = Jwks::from_bytes(format!(r#"{{"keys": [{}]}}"#, RSA_JWK_1).as_bytes()).unwrap();
+        let validator = JwtValidator::jwks(jwks);
+
+        // HS256 signed with the public modulus must not match an RS256 key
+        let key = EncodingKey::from_secret(RSA_JWK_1.as_bytes());
+        let token = sign(Algorithm::HS256, Some("key-1"), &key);
+        assert!(validator.validate(&token).is_err());
+    }
+
+    #[test]
+    fn test_asymmetric_key_formats() {
+        let es256 = sign(
+            Algorithm::ES256,
+            None,
+            &EncodingKey::from_ec_pem(EC_KEY).unwrap(),
+        );
+        for validator in [
+            JwtValidator::es256_pem(EC_PUB_PEM).unwrap(),
+            JwtValidator::es256_der(EC_PUB_DER).unwrap(),
+            JwtValidator::from_jwk(EC_JWK).unwrap(),
+        ] {
+            assert_eq!(validator.validate(&es256).unwrap().user_id(), "user-123");
+        }
+
+        let eddsa = sign(
+            Algorithm::EdDSA,
+            None,
+            &EncodingKey::from_ed_pem(ED_KEY).unwrap(),
+        );
+        for validator in [
+            JwtValidator::eddsa_pem(ED_PUB_PEM).unwrap(),
+            JwtValidator::eddsa_der(ED_PUB_DER).unwrap(),
+            JwtValidator::from_jwk(ED_JWK).unwrap(),
+        ] {
+            assert!(validator.validate(&eddsa).is_ok());
+            assert!(validator.validate(&es256).is_err());
+        }
+
+        let rsa = EncodingKey::from_rsa_pem(RSA_KEY_1).unwrap();
+        let ps256 = sign(Algorithm::PS256, None, &rsa);
+        let rs256 = sign(Algorithm::RS256, None, &rsa);
+        for validator in [
+            JwtValidator::ps256_pem(RSA_PUB_PEM).unwrap(),
+            JwtValidator::ps256_der(RSA_PUB_DER).unwrap(),
+        ] {
+            assert!(validator.validate(&ps256).is_ok());
+            assert!(validator.validate(&rs256).is_err());
+        }
+        // The JWK pins RS256
+        let validator = JwtValidator::from_jwk(RSA_JWK_1.as_bytes()).unwrap();
+        assert!(validator.validate(&rs256).is_ok());
+        assert!(validator.validate(&ps256).is_err());
+
+        assert!(JwtValidator::es256_der(b"not a key").is_err());
+        assert!(
+            JwtValidator::from_jwk(br#"{"kty": "RSA", "use": "enc", "n": "AQAB", "e": "AQAB"}"#)
+                .is_err()
+        );
+    }
+
+    #[test]
+    fn test_with_algorithms() {
+        let rsa = EncodingKey::from_rsa_pem(RSA_KEY_1).unwrap();
+        let validator = JwtValidator::rs256_pem(RSA_PUB_PEM)
+            .unwrap()
+            .with_algorithms(&[Algorithm::RS256, Algorithm::PS256])
+            .unwrap();
+        assert!(
+            validator
+                .validate(&sign(Algorithm::RS256, None, &rsa))
+                .is_ok()
+        );
+        assert!(
+            validator
+                .validate(&sign(Algorithm::PS256, None, &rsa))
+                .is_ok()
+        );
+
+        // Algorithm confusion: the RSA public key used as an HMAC secret
+        let confused = sign(
+            Algorithm::HS256,
+            None,
+            &EncodingKey::from_secret(RSA_PUB_PEM),
+        );
+        assert!(validator.validate(&confused).is_err());
+        let mixed = JwtValidator::rs256_pem(RSA_PUB_PEM)
+            .unwrap()
+            .with_algorithms(&[Algorithm::RS256, Algorithm::HS256]);
+        assert!(matches!(mixed, Err(JwtError::Algorithms(_))));
+        assert!(
+            JwtValidator::hs256(b"secret")
+                .with_algorithms(&[Algorithm::RS256])
+                .is_err()
+        );
+        assert!(JwtValidator::hs256(b"secret").with_algorithms(&[]).is_err());
+
+        // A JWKS ignores HMAC keys unless HMAC is opted into, on its own
+        let oct = br#"{"keys": [{"kty": "oct", "kid": "hmac", "k": "c2VjcmV0"}]}"#;
+        let hs256 = sign(
+            Algorithm::HS256,
+            Some("hmac"),
+            &EncodingKey::from_secret(b"secret"),
+        );
+        let validator = JwtValidator::jwks(Jwks::from_bytes(oct).unwrap());
+        assert!(validator.validate(&hs256).is_err());
+        let validator = validator.with_algorithms(&[Algorithm::HS256]).unwrap();
+        assert!(validator.validate(&hs256).is_ok());
+        let mixed = JwtValidator::jwks(Jwks::from_bytes(oct).unwrap())
+            .with_algorithms(&[Algorithm::ES256, Algorithm::HS256]);
+        assert!(matches!(mixed, Err(JwtError::Algorithms(_))));
+    }
+
+    fn hs256_token(payload: &Value) -> String {
+        encode(
+            &Header::new(Algorithm::HS256),
+            payload,
+            &EncodingKey::from_secret(b"secret"),
+        )
+        .unwrap()
+    }
+
+    #[test]
+    fn test_audience_and_extra_claims() {
+        let validator = JwtValidator::hs256(b"secret").with_audience("mcp");
+        let token = hs256_token(&serde_json::json!({
+            "sub": "user-123",
+            "exp": u64::MAX / 2,
+            "aud": ["other", "mcp"],
+            "scope": ["read", "write"],
+            "https://example.com/org_id": "org-9",
+            "realm_access": { "roles": ["admin"] },
+        }));
+        let claims = validator.validate(&token).unwrap();
+        assert_eq!(claims.aud, vec!["other", "mcp"]);
+        assert!(claims.has_scope("write"));
+        assert_eq!(
+            claims.claim("https://example.com/org_id"),
+            Some(&Value::from("org-9"))
+        );
+        assert_eq!(
+            claims.claim("/realm_access/roles/0"),
+            Some(&Value::from("admin"))
+        );
+        assert_eq!(claims.claim("missing"), None);
+
+        let token = hs256_token(&serde_json::json!({
+            "sub": "user-123", "exp": u64::MAX / 2, "aud": "mcp",
+        }));
+        assert_eq!(validator.validate(&token).unwrap().aud, vec!["mcp"]);
+    }
+
+    #[test]
+    fn test_claim_mapping() {
+        let payload = serde_json::json!({
+            "sub": "ignored",
+            "oid": 42,
+            "exp": u64::MAX / 2,
+            "scope": "openid",
+            "scp": "files.read files.write",
+            "https://example.com/claims": { "org_id": "org-9", "roles": ["admin"] },
+        });
+        let mapping = ClaimMapping::new()
+            .user("oid")
+            .tenant("/https:~1~1example.com~1claims/org_id")
+            .scopes(&["scp", "/https:~1~1example.com~1claims/roles"]);
+        let validator = JwtValidator::hs256(b"secret").with_claim_mapping(mapping);
+
+        let claims = validator.validate(&hs256_token(&payload)).unwrap();
+        assert_eq!(claims.user_id(), "42");
+        assert_eq!(claims.tenant_id(), "org-9");
+        assert_eq!(
+            claims.scopes().collect::<Vec<_>>(),
+            vec!["files.read", "files.write", "admin"]
+        );
+        assert!(!claims.has_scope("openid"));
+
+        // A missing user claim is an error; a missing tenant falls back to the user
+        let validator = JwtValidator::hs256(b"secret")
+            .with_claim_mapping(ClaimMapping::new().user("upn").tenant("org_id"));
+        assert!(matches!(
+            validator.validate(&hs256_token(&payload)),
+            Err(JwtError::MissingClaim(name)) if name == "upn"
+        ));
+        let validator =
+            JwtValidator::hs256(b"secret").with_claim_mapping(ClaimMapping::new().tenant("org_id"));
+        assert_eq!(
+            validator
+                .validate(&hs256_token(&payload))
+                .unwrap()
+                .tenant_id(),
+            "ignored"
+        );
+    }
 }
@@ -1,7 +1,23 @@
-//! JWT Authentication
+//! Authentication
 //!
-//! Validates JWT tokens and extracts claims for multi-tenancy.
+//! Identifies callers for hosted servers: JWTs (with JWKS), API keys, token
+//! introspection, client certificates and signed requests, all producing
+//! `Claims` for multi-tenancy.
 
+mod api_key;
+mod authenticator;
+mod client_cert;
+mod fetch;
+mod introspection;
+mod jwks;
 mod jwt;
+mod signature;
 
-pub use jwt::{Claims, JwtError, JwtValidator};
+pub use api_key::ApiKeys;
+pub use authenticator::{AuthChain, AuthError, AuthRequest, Authenticator};
+pub use client_cert::ClientCertificates;
+pub use introspection::{Introspection, IntrospectionFetcher};
+pub use jsonwebtoken::Algorithm;
+pub use jwks::{Jwks, JwksFetcher};
+pub use jwt::{ClaimMapping, Claims, JwtError, JwtValidator};
+pub use signature::HmacSignatures;
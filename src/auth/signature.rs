@@ -0,0 +1,290 @@
+//! HMAC-Signed Requests
+//!
+//! Callers sharing a secret sign each request with HMAC-SHA256 over
+//!
+//! ```text
+//! {timestamp}\n{METHOD}\n{url}\n{hex SHA-256 of the body}
+//! ```
+//!
+//! and send `X-Key-Id`, `X-Timestamp` (Unix seconds) and `X-Signature`
+//! (lowercase hex). Requests outside the allowed clock skew, or replaying a
+//! signature already seen, are refused.
+
+use super::authenticator::{AuthError, AuthRequest, Authenticator};
+use super::jwt::Claims;
+use ring::digest::{SHA256, digest};
+use ring::hmac;
+use std::collections::HashMap;
+use std::sync::Mutex;
+use std::time::{Duration, SystemTime, UNIX_EPOCH};
+
+const KEY_ID_HEADER: &str = "X-Key-Id";
+const TIMESTAMP_HEADER: &str = "X-Timestamp";
+const SIGNATURE_HEADER: &str = "X-Signature";
+
+/// How far a request's timestamp may be from our clock
+const DEFAULT_MAX_SKEW: Duration = Duration::from_secs(5 * 60);
+
+/// Authenticates callers by an HMAC-SHA256 signature over the request
+///
+/// # Example
+/// ```ignore
+/// let signed = HmacSignatures::new().key("ingest", &secret, Claims {
+///     sub: "ingest-worker".into(),
+///     ..Default::default()
+/// });
+///
+/// // Client side
+/// let signature = HmacSignatures::sign(&secret, timestamp, "POST", "/mcp", body);
+/// ```
+pub struct HmacSignatures {
+    keys: HashMap<String, (hmac::Key, Claims)>,
+    max_skew: Duration,
+    /// Signatures seen, with the time after which they are too old to replay
+    seen: Mutex<HashMap<String, u64>>,
+}
+
+impl Default for HmacSignatures {
+    fn default() -> Self {
+        Self {
+            keys: HashMap::new(),
+            max_skew: DEFAULT_MAX_SKEW,
+            seen: Mutex::new(HashMap::new()),
+        }
+    }
+}
+
+impl HmacSignatures {
+    /// Create with no keys
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Accept signatures made with `secret` under `key_id`, identifying the caller with `claims`
+    pub fn key(mut self, key_id: impl Into<String>, secret: &[u8], claims: Claims) -> Self {
+        let key = hmac::Key::new(hmac::HMAC_SHA256, secret);
+        self.keys.insert(key_id.into(), (key, claims));
+        self
+    }
+
+    /// Allow timestamps this far from our clock (default: 5 minutes)
+    pub fn with_max_skew(mut self, skew: Duration) -> Self {
+        self.max_skew = skew;
+        self
+    }
+
+    /// The `X-Signature` value for a request, for clients and tests
+    pub fn sign(secret: &[u8], timestamp: u64, method: &str, url: &str, body: &[u8]) -> String {
+        let key = hmac::Key::new(hmac::HMAC_SHA256, secret);
+        hex(hmac::sign(&key, &message(timestamp, method, url, body)).as_ref())
+    }
+
+    /// Remember `signature` until `expires`, refusing it if already seen
+    fn check_replay(&self, signature: &str, now: u64, expires: u64) -> Result<(), AuthError> {
+        let mut seen = self.seen.lock().unwrap_or_else(|e| e.into_inner());
+        seen.retain(|_, expiry| *expiry >= now);
+        if seen.insert(signature.to_string(), expires).is_some() {
+            return Err(AuthError::Rejected("Replayed request signature".into()));
+        }
+        Ok(())
+    }
+}
+
+impl Authenticator for HmacSignatures {
+    fn authenticate(&self, request: &AuthRequest<'_>) -> Result<Option<Claims>, AuthError> {
+        let key_id = request.header(KEY_ID_HEADER);
+        let timestamp = request.header(TIMESTAMP_HEADER);
+        let signature = request.header(SIGNATURE_HEADER);
+        let (key_id, timestamp, signature) = match (key_id, timestamp, signature) {
+            (None, None, None) => return Ok(None),
+            (Some(key_id), Some(timestamp), Some(signature)) => (key_id, timestamp, signature),
+            _ => {
+                return Err(AuthError::Malformed(format!(
+                    "signed requests need {}, {} and {}",
+                    KEY_ID_HEADER, TIMESTAMP_HEADER, SIGNATURE_HEADER
+                )));
+            }
+        };
+
+        let Some((key, claims)) = self.keys.get(key_id) else {
+            return Err(AuthError::Rejected(format!("Unknown key id: {}", key_id)));
+        };
+        let timestamp: u64 = timestamp
+            .trim()
+            .parse()
+            .map_err(|_| AuthError::Malformed(format!("invalid {}", TIMESTAMP_HEADER)))?;
+        let tag = unhex(signature.trim())
+            .ok_or_else(|| AuthError::Malformed(format!("invalid {}", SIGNATURE_HEADER)))?;
+
+        let now = SystemTime::now()
+            .duration_since(UNIX_EPOCH)
+            .unwrap_or_default()
+            .as_secs();
+        if now.abs_diff(timestamp) > self.max_skew.as_secs() {
+            return Err(AuthError::Rejected(
+                "Request timestamp outside the allowed window".into(),
+            ));
+        }
+
+        let message = message(timestamp, request.method, request.url, request.body);
+        hmac::verify(key, &message, &tag)
+            .map_err(|_| AuthError::Rejected("Invalid request signature".into()))?;
+
+        self.check_replay(&hex(&tag), now, timestamp + self.max_skew.as_secs())?;
+        Ok(Some(claims.clone()))
+    }
+
+    /// The signature covers the body, so signed requests need it read first
+    fn needs_body(&self, request: &AuthRequest<'_>) -> bool {
+        [KEY_ID_HEADER, TIMESTAMP_HEADER, SIGNATURE_HEADER]
+            .iter()
+            .any(|name| request.header(name).is_some())
+    }
+}
+
+/// The string a signature covers
+fn message(timestamp: u64, method: &str, url: &str, body: &[u8]) -> Vec<u8> {
+    format!(
+        "{}\n{}\n{}\n{}",
+        timestamp,
+        method.to_ascii_uppercase(),
+        url,
+        hex(digest(&SHA256, body).as_ref())
+    )
+    .into_bytes()
+}
+
+fn hex(bytes: &[u8]) -> String {
+    bytes.iter().map(|b| format!("{:02x}", b)).collect()
+}
+
+fn unhex(text: &str) -> Option<Vec<u8>> {
+    if !text.is_ascii() || text.len() % 2 != 0 {
+        return None;
+    }
+    (0..text.len())
+        .step_by(2)
+        .map(|i| u8::from_str_radix(&text[i..i + 2], 16).ok())
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::auth::authenticator::test_request;
+
+    const SECRET: &[u8] = b"shared-secret";
+
+    fn now() -> u64 {
+        SystemTime::now()
+            .duration_since(UNIX_EPOCH)
+            .unwrap()
+            .as_secs()
+    }
+
+    fn signed(key_id: &str, timestamp: u64, signature: String) -> Vec<(String, String)> {
+        vec![
+            ("X-Key-Id".into(), key_id.into()),
+            ("X-Timestamp".into(), timestamp.to_string()),
+            ("X-Signature".into(), signature),
+        ]
+    }
+
+    fn request<'a>(headers: &'a [(String, String)], body: &'a [u8]) -> AuthRequest<'a> {
+        AuthRequest {
+            url: "/mcp?x=1",
+            body,
+            ..test_request(headers)
+        }
+    }
+
+    fn ingest() -> HmacSignatures {
+        HmacSignatures::new().key(
+            "ingest",
+            SECRET,
+            Claims {
+                sub: "ingest-worker".into(),
+                ..Default::default()
+            },
+        )
+    }
+
+    #[test]
+    fn test_signed_request() {
+        let signatures = ingest();
+        let body = br#"{"jsonrpc":"2.0","id":1,"method":"ping"}"#;
+        let timestamp = now();
+        let signature = HmacSignatures::sign(SECRET, timestamp, "post", "/mcp?x=1", body);
+        assert_eq!(signature.len(), 64);
+
+        let headers = signed("ingest", timestamp, signature.clone());
+        let claims = signatures.authenticate(&request(&headers, body)).unwrap();
+        assert_eq!(claims.unwrap().user_id(), "ingest-worker");
+
+        // The same signature can't be used twice
+        let error = signatures
+            .authenticate(&request(&headers, body))
+            .unwrap_err();
+        assert_eq!(
+            error,
+            AuthError::Rejected("Replayed request signature".into())
+        );
+
+        // Any change to the body breaks the signature
+        let error = ingest()
+            .authenticate(&request(&headers, b"{}"))
+            .unwrap_err();
+        assert_eq!(
+            error,
+            AuthError::Rejected("Invalid request signature".into())
+        );
+
+        let headers = signed("other", timestamp, signature);
+        assert!(ingest().authenticate(&request(&headers, body)).is_err());
+    }
+
+    #[test]
+    fn test_signed_request_needs_body() {
+        let signatures = ingest();
+        assert!(!signatures.needs_body(&request(&[], b"")));
+        let headers = signed("ingest", now(), "00".into());
+        assert!(signatures.needs_body(&request(&headers, b"")));
+    }
+
+    #[test]
+    fn test_signed_request_errors() {
+        let signatures = ingest();
+        assert!(matches!(
+            signatures.authenticate(&request(&[], b"")),
+            Ok(None)
+        ));
+
+        let stale = now() - 600;
+        let signature = HmacSignatures::sign(SECRET, stale, "POST", "/mcp?x=1", b"");
+        let headers = signed("ingest", stale, signature);
+        let error = signatures
+            .authenticate(&request(&headers, b""))
+            .unwrap_err();
+        assert!(error.to_string().contains("outside the allowed window"));
+
+        let headers = signed("ingest", now(), "zz".into());
+        assert!(matches!(
+            signatures.authenticate(&request(&headers, b"")),
+            Err(AuthError::Malformed(_))
+        ));
+
+        let headers = [("X-Key-Id".to_string(), "ingest".to_string())];
+        assert!(matches!(
+            signatures.authenticate(&request(&headers, b"")),
+            Err(AuthError::Malformed(_))
+        ));
+    }
+
+    #[test]
+    fn test_hex() {
+        assert_eq!(hex(&[0x00, 0xab, 0x7f]), "00ab7f");
+        assert_eq!(unhex("00AB7f"), Some(vec![0x00, 0xab, 0x7f]));
+        assert_eq!(unhex("abc"), None);
+        assert_eq!(unhex("é0"), None);
+    }
+}
@@ -6,9 +6,14 @@
 //!
 //! - `schema` (default) - JSON Schema generation for tools via schemars
 //! - `http` - Streamable HTTP transport via tiny_http
-//! - `auth` - JWT validation for hosted deployments
+//! - `tls` - HTTPS for `HttpServer` via tiny_http's rustls support
+//! - `websocket` - WebSocket transport via tungstenite (sync)
+//! - `tracing` - Spans per request and tool call, with W3C `traceparent` support
+//! - `auth` - JWT, API key and other authentication for hosted deployments
 //! - `hosted` - Enables both `http` and `auth`
 
+pub mod logging;
+pub mod metrics;
 pub mod pagination;
 pub mod server;
 pub mod transport;
@@ -17,11 +22,36 @@ pub mod types;
 #[cfg(feature = "auth")]
 pub mod auth;
 
+#[cfg(feature = "tracing")]
+pub mod telemetry;
+
 // Re-export commonly used types
+pub use logging::LogConfig;
+pub use metrics::Metrics;
 pub use pagination::{DEFAULT_PAGE_SIZE, PageState, paginate};
-pub use server::{LogLevel, PromptDef, Resource, Server, ServerConfig, Tool, ToolEnv};
-pub use transport::{StdioTransport, Transport};
+pub use server::{
+    LifecycleState, LogLevel, PromptDef, Registry, RegistryHandle, Resource, Server, ServerConfig,
+    Session, Tool, ToolEnv,
+};
+pub use transport::{Framing, StdioTransport, StreamTransport, Transport};
 pub use types::*;
 
+#[cfg(any(unix, feature = "websocket"))]
+pub use transport::ShutdownHandle;
+
+#[cfg(unix)]
+pub use transport::UnixServer;
+
 #[cfg(feature = "http")]
-pub use transport::{HttpServer, HttpTransport};
+pub use transport::{
+    Cors, HttpServer, HttpTransport, MountedApp, ProtectedResource, RouteRequest, RouteResponse,
+};
+
+#[cfg(feature = "tls")]
+pub use transport::{TlsConfig, TlsReloader};
+
+#[cfg(feature = "websocket")]
+pub use transport::{WebSocketServer, WebSocketTransport};
+
+#[cfg(feature = "tracing")]
+pub use telemetry::TraceParent;
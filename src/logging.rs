@@ -0,0 +1,193 @@
+//! Logging
+//!
+//! Diagnostics go through the `log` facade, so the application picks the
+//! logger, destination and level (filter on the `sml_mcps` target). Message
+//! bodies are only logged when enabled here, and tool arguments and results
+//! pass through the redaction hooks first.
+
+use crate::types::{JsonRpcMessage, JsonRpcNotification, JsonRpcRequest};
+use serde_json::Value;
+use std::sync::Arc;
+
+/// Redaction hook: receives the tool name and a value to edit in place
+pub type RedactFn = dyn Fn(&str, &mut Value) + Send + Sync;
+
+/// What the server may write to the log
+///
+/// # Example
+/// ```ignore
+/// let config = ServerConfig {
+///     logging: LogConfig::default()
+///         .with_bodies(true)
+///         .redact_arguments(|tool, args| {
+///             if tool == "login" {
+///                 args["password"] = "***".into();
+///             }
+///         }),
+///     ..Default::default()
+/// };
+/// ```
+#[derive(Clone, Default)]
+pub struct LogConfig {
+    bodies: bool,
+    redact_arguments: Option<Arc<RedactFn>>,
+    redact_results: Option<Arc<RedactFn>>,
+}
+
+impl LogConfig {
+    /// Log JSON-RPC message bodies at debug level (default: off)
+    pub fn with_bodies(mut self, enabled: bool) -> Self {
+        self.bodies = enabled;
+        self
+    }
+
+    /// Edit `tools/call` arguments before they are logged
+    pub fn redact_arguments<F>(mut self, redact: F) -> Self
+    where
+        F: Fn(&str, &mut Value) + Send + Sync + 'static,
+    {
+        self.redact_arguments = Some(Arc::new(redact));
+        self
+    }
+
+    /// Edit `tools/call` results before they are logged
+    pub fn redact_results<F>(mut self, redact: F) -> Self
+    where
+        F: Fn(&str, &mut Value) + Send + Sync + 'static,
+    {
+        self.redact_results = Some(Arc::new(redact));
+        self
+    }
+
+    /// True if message bodies should be logged
+    pub fn bodies_enabled(&self) -> bool {
+        self.bodies && log::log_enabled!(log::Level::Debug)
+    }
+
+    /// Log an incoming request
+    pub(crate) fn request(&self, request: &JsonRpcRequest) {
+        if self.bodies_enabled() {
+            if let Some(body) = self.render_request(request) {
+                log::debug!("<- {}", body);
+            }
+        }
+    }
+
+    /// Log an incoming notification
+    pub(crate) fn notification(&self, notification: &JsonRpcNotification) {
+        if self.bodies_enabled() {
+            if let Ok(body) = serde_json::to_string(notification) {
+                log::debug!("<- {}", body);
+            }
+        }
+    }
+
+    /// Log the response to `request`
+    pub(crate) fn response(&self, request: &JsonRpcRequest, response: &JsonRpcMessage) {
+        if self.bodies_enabled() {
+            if let Some(body) = self.render_response(request, response) {
+                log::debug!("-> {}", body);
+            }
+        }
+    }
+
+    fn render_request(&self, request: &JsonRpcRequest) -> Option<String> {
+        let mut value = serde_json::to_value(request).ok()?;
+        if let (Some(redact), Some(tool)) = (&self.redact_arguments, tool_name(request)) {
+            if let Some(arguments) = value.pointer_mut("/params/arguments") {
+                redact(tool, arguments);
+            }
+        }
+        Some(value.to_string())
+    }
+
+    fn render_response(
+        &self,
+        request: &JsonRpcRequest,
+        response: &JsonRpcMessage,
+    ) -> Option<String> {
+        let mut value = serde_json::to_value(response).ok()?;
+        if let (Some(redact), Some(tool)) = (&self.redact_results, tool_name(request)) {
+            if let Some(result) = value.get_mut("result") {
+                redact(tool, result);
+            }
+        }
+        Some(value.to_string())
+    }
+}
+
+impl std::fmt::Debug for LogConfig {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.debug_struct("LogConfig")
+            .field("bodies", &self.bodies)
+            .field("redact_arguments", &self.redact_arguments.is_some())
+            .field("redact_results", &self.redact_results.is_some())
+            .finish()
+    }
+}
+
+/// Tool name of a `tools/call` request
+fn tool_name(request: &JsonRpcRequest) -> Option<&str> {
+    if request.method != "tools/call" {
+        return None;
+    }
+    request.params.as_ref()?.get("name")?.as_str()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use serde_json::json;
+
+    fn call(arguments: Value) -> JsonRpcRequest {
+        let message = JsonRpcMessage::request(
+            1i64,
+            "tools/call",
+            Some(json!({ "name": "login", "arguments": arguments })),
+        );
+        match message {
+            JsonRpcMessage::Request(request) => request,
+            _ => unreachable!(),
+        }
+    }
+
+    #[test]
+    fn test_bodies_off_by_default() {
+        assert!(!LogConfig::default().bodies_enabled());
+    }
+
+    #[test]
+    fn test_redacts_tool_arguments() {
+        let config = LogConfig::default().redact_arguments(|tool, args| {
+            assert_eq!(tool, "login");
+            args["password"] = "***".into();
+        });
+
+        let body = config
+            .render_request(&call(json!({ "user": "alice", "password": "hunter2" })))
+            .unwrap();
+        assert!(body.contains("alice"));
+        assert!(body.contains("***"));
+        assert!(!body.contains("hunter2"));
+
+        // Other methods are left alone
+        let ping = match JsonRpcMessage::request(2i64, "ping", None) {
+            JsonRpcMessage::Request(request) => request,
+            _ => unreachable!(),
+        };
+        assert!(config.render_request(&ping).unwrap().contains("ping"));
+    }
+
+    #[test]
+    fn test_redacts_tool_results() {
+        let config = LogConfig::default().redact_results(|_, result| {
+            *result = json!("[redacted]");
+        });
+        let request = call(json!({}));
+        let response = JsonRpcMessage::response(1i64, json!({ "content": "token-123" }));
+
+        let body = config.render_response(&request, &response).unwrap();
+        assert!(body.contains("[redacted]"));
+        assert!(!body.contains("token-123"));
+    }
+}
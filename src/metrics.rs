@@ -0,0 +1,343 @@
+//! Metrics
+//!
+//! A small in-process registry of counters, gauges and histograms, rendered
+//! in the Prometheus text format. `Server` and `HttpServer` record the
+//! built-in series below; tools can add their own through `ToolEnv::metrics`.
+
+use std::collections::BTreeMap;
+use std::fmt::Write;
+use std::sync::{Arc, Mutex, MutexGuard};
+
+/// JSON-RPC requests handled, by `method`
+pub const REQUESTS_TOTAL: &str = "mcp_requests_total";
+/// JSON-RPC error responses, by `code`
+pub const ERRORS_TOTAL: &str = "mcp_errors_total";
+/// Tool execution time in seconds, by `tool`
+pub const TOOL_DURATION_SECONDS: &str = "mcp_tool_duration_seconds";
+/// Open HTTP sessions
+pub const ACTIVE_SESSIONS: &str = "mcp_active_sessions";
+/// HTTP responses sent as an SSE stream
+pub const SSE_STREAMS_TOTAL: &str = "mcp_sse_streams_total";
+
+/// Histogram bucket upper bounds, in seconds
+const DEFAULT_BUCKETS: [f64; 11] = [
+    0.005, 0.01, 0.025, 0.05, 0.1, 0.25, 0.5, 1.0, 2.5, 5.0, 10.0,
+];
+
+type Labels = Vec<(String, String)>;
+
+#[derive(Clone, Copy, PartialEq, Eq)]
+enum Kind {
+    Counter,
+    Gauge,
+    Histogram,
+}
+
+impl Kind {
+    fn as_str(self) -> &'static str {
+        match self {
+            Kind::Counter => "counter",
+            Kind::Gauge => "gauge",
+            Kind::Histogram => "histogram",
+        }
+    }
+}
+
+enum Series {
+    Value(f64),
+    Histogram {
+        buckets: Vec<u64>,
+        sum: f64,
+        count: u64,
+    },
+}
+
+struct Family {
+    kind: Kind,
+    help: Option<String>,
+    series: BTreeMap<Labels, Series>,
+}
+
+/// Shared metrics registry
+///
+/// Cheap to clone; clones update the same registry. A name keeps the kind
+/// it was first used with - updates of another kind are ignored.
+///
+/// # Example
+/// ```ignore
+/// env.metrics().inc("cache_hits_total", &[("cache", "users")]);
+/// env.metrics().observe("upstream_seconds", &[], elapsed.as_secs_f64());
+/// ```
+#[derive(Clone)]
+pub struct Metrics {
+    families: Arc<Mutex<BTreeMap<String, Family>>>,
+}
+
+impl Default for Metrics {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl Metrics {
+    /// Create a registry with the built-in series described
+    pub fn new() -> Self {
+        let metrics = Self {
+            families: Arc::new(Mutex::new(BTreeMap::new())),
+        };
+        metrics.register(REQUESTS_TOTAL, Kind::Counter, "JSON-RPC requests handled");
+        metrics.register(ERRORS_TOTAL, Kind::Counter, "JSON-RPC error responses");
+        metrics.register(
+            TOOL_DURATION_SECONDS,
+            Kind::Histogram,
+            "Tool execution time in seconds",
+        );
+        metrics.register(ACTIVE_SESSIONS, Kind::Gauge, "Open HTTP sessions");
+        metrics.register(SSE_STREAMS_TOTAL, Kind::Counter, "Responses sent as SSE");
+        // Unlabelled series are exported as zero until first updated
+        metrics.set_gauge(ACTIVE_SESSIONS, &[], 0.0);
+        metrics.add(SSE_STREAMS_TOTAL, &[], 0.0);
+        metrics
+    }
+
+    /// Set the `# HELP` text for a metric
+    pub fn describe(&self, name: &str, help: &str) {
+        if let Some(family) = self.lock().get_mut(name) {
+            family.help = Some(help.to_string());
+        }
+    }
+
+    /// Increment a counter by one
+    pub fn inc(&self, name: &str, labels: &[(&str, &str)]) {
+        self.add(name, labels, 1.0);
+    }
+
+    /// Increment a counter by `value` (negative values are ignored)
+    pub fn add(&self, name: &str, labels: &[(&str, &str)], value: f64) {
+        if value >= 0.0 {
+            self.update_value(name, Kind::Counter, labels, |v| *v += value);
+        }
+    }
+
+    /// Set a gauge
+    pub fn set_gauge(&self, name: &str, labels: &[(&str, &str)], value: f64) {
+        self.update_value(name, Kind::Gauge, labels, |v| *v = value);
+    }
+
+    /// Record an observation in a histogram
+    pub fn observe(&self, name: &str, labels: &[(&str, &str)], value: f64) {
+        let mut families = self.lock();
+        let family = families
+            .entry(name.to_string())
+            .or_insert_with(|| Family::new(Kind::Histogram));
+        if family.kind != Kind::Histogram {
+            return;
+        }
+
+        let series = family
+            .series
+            .entry(to_labels(labels))
+            .or_insert_with(|| Series::Histogram {
+                buckets: vec![0; DEFAULT_BUCKETS.len()],
+                sum: 0.0,
+                count: 0,
+            });
+        if let Series::Histogram {
+            buckets,
+            sum,
+            count,
+        } = series
+        {
+            for (bucket, bound) in buckets.iter_mut().zip(DEFAULT_BUCKETS) {
+                if value <= bound {
+                    *bucket += 1;
+                }
+            }
+            *sum += value;
+            *count += 1;
+        }
+    }
+
+    /// Current value of a counter or gauge
+    pub fn value(&self, name: &str, labels: &[(&str, &str)]) -> Option<f64> {
+        match self.lock().get(name)?.series.get(&to_labels(labels))? {
+            Series::Value(v) => Some(*v),
+            Series::Histogram { .. } => None,
+        }
+    }
+
+    /// Render every metric in the Prometheus text exposition format
+    pub fn render(&self) -> String {
+        let families = self.lock();
+        let mut out = String::new();
+
+        for (name, family) in families.iter() {
+            if let Some(help) = &family.help {
+                let _ = writeln!(out, "# HELP {} {}", name, help.replace('\n', " "));
+            }
+            let _ = writeln!(out, "# TYPE {} {}", name, family.kind.as_str());
+
+            for (labels, series) in &family.series {
+                match series {
+                    Series::Value(v) => {
+                        let _ = writeln!(out, "{}{} {}", name, format_labels(labels, None), v);
+                    }
+                    Series::Histogram {
+                        buckets,
+                        sum,
+                        count,
+                    } => {
+                        for (bucket, bound) in buckets.iter().zip(DEFAULT_BUCKETS) {
+                            let le = bound.to_string();
+                            let labels = format_labels(labels, Some(&le));
+                            let _ = writeln!(out, "{}_bucket{} {}", name, labels, bucket);
+                        }
+                        let inf = format_labels(labels, Some("+Inf"));
+                        let _ = writeln!(out, "{}_bucket{} {}", name, inf, count);
+                        let labels = format_labels(labels, None);
+                        let _ = writeln!(out, "{}_sum{} {}", name, labels, sum);
+                        let _ = writeln!(out, "{}_count{} {}", name, labels, count);
+                    }
+                }
+            }
+        }
+
+        out
+    }
+
+    fn register(&self, name: &str, kind: Kind, help: &str) {
+        let mut family = Family::new(kind);
+        family.help = Some(help.to_string());
+        self.lock().insert(name.to_string(), family);
+    }
+
+    fn update_value(
+        &self,
+        name: &str,
+        kind: Kind,
+        labels: &[(&str, &str)],
+        update: impl FnOnce(&mut f64),
+    ) {
+        let mut families = self.lock();
+        let family = families
+            .entry(name.to_string())
+            .or_insert_with(|| Family::new(kind));
+        if family.kind != kind {
+            return;
+        }
+
+        let series = family
+            .series
+            .entry(to_labels(labels))
+            .or_insert(Series::Value(0.0));
+        if let Series::Value(v) = series {
+            update(v);
+        }
+    }
+
+    fn lock(&self) -> MutexGuard<'_, BTreeMap<String, Family>> {
+        // A panic mid-update leaves at worst one stale sample
+        self.families.lock().unwrap_or_else(|e| e.into_inner())
+    }
+}
+
+impl Family {
+    fn new(kind: Kind) -> Self {
+        Self {
+            kind,
+            help: None,
+            series: BTreeMap::new(),
+        }
+    }
+}
+
+fn to_labels(labels: &[(&str, &str)]) -> Labels {
+    let mut labels: Labels = labels
+        .iter()
+        .map(|(k, v)| (k.to_string(), v.to_string()))
+        .collect();
+    labels.sort();
+    labels
+}
+
+/// `{a="1",b="2"}`, with an optional `le` label for histogram buckets
+fn format_labels(labels: &Labels, le: Option<&str>) -> String {
+    let mut pairs: Vec<String> = labels
+        .iter()
+        .map(|(k, v)| format!("{}=\"{}\"", k, escape(v)))
+        .collect();
+    if let Some(le) = le {
+        pairs.push(format!("le=\"{}\"", le));
+    }
+    if pairs.is_empty() {
+        String::new()
+    } else {
+        format!("{{{}}}", pairs.join(","))
+    }
+}
+
+fn escape(value: &str) -> String {
+    value
+        .replace('\\', "\\\\")
+        .replace('"', "\\\"")
+        .replace('\n', "\\n")
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_counters_and_gauges() {
+        let metrics = Metrics::new();
+        metrics.inc(REQUESTS_TOTAL, &[("method", "ping")]);
+        metrics.inc(REQUESTS_TOTAL, &[("method", "ping")]);
+        metrics.add(REQUESTS_TOTAL, &[("method", "ping")], -5.0);
+        metrics.set_gauge(ACTIVE_SESSIONS, &[], 3.0);
+
+        assert_eq!(
+            metrics.value(REQUESTS_TOTAL, &[("method", "ping")]),
+            Some(2.0)
+        );
+        assert_eq!(metrics.value(ACTIVE_SESSIONS, &[]), Some(3.0));
+
+        // Clones share the registry; kind mismatches are ignored
+        let clone = metrics.clone();
+        clone.set_gauge(REQUESTS_TOTAL, &[("method", "ping")], 100.0);
+        assert_eq!(
+            metrics.value(REQUESTS_TOTAL, &[("method", "ping")]),
+            Some(2.0)
+        );
+
+        let text = metrics.render();
+        assert!(text.contains("# TYPE mcp_requests_total counter\n"));
+        assert!(text.contains("mcp_requests_total{method=\"ping\"} 2\n"));
+        assert!(text.contains("mcp_active_sessions 3\n"));
+    }
+
+    #[test]
+    fn test_histogram_rendering() {
+        let metrics = Metrics::new();
+        metrics.observe(TOOL_DURATION_SECONDS, &[("tool", "echo")], 0.02);
+        metrics.observe(TOOL_DURATION_SECONDS, &[("tool", "echo")], 3.0);
+
+        let text = metrics.render();
+        assert!(text.contains("# TYPE mcp_tool_duration_seconds histogram\n"));
+        assert!(text.contains("mcp_tool_duration_seconds_bucket{tool=\"echo\",le=\"0.01\"} 0\n"));
+        assert!(text.contains("mcp_tool_duration_seconds_bucket{tool=\"echo\",le=\"0.025\"} 1\n"));
+        assert!(text.contains("mcp_tool_duration_seconds_bucket{tool=\"echo\",le=\"+Inf\"} 2\n"));
+        assert!(text.contains("mcp_tool_duration_seconds_sum{tool=\"echo\"} 3.02\n"));
+        assert!(text.contains("mcp_tool_duration_seconds_count{tool=\"echo\"} 2\n"));
+    }
+
+    #[test]
+    fn test_custom_metrics_and_escaping() {
+        let metrics = Metrics::new();
+        metrics.inc("cache_hits_total", &[("key", "a\"b\\c")]);
+        metrics.describe("cache_hits_total", "Cache hits");
+
+        let text = metrics.render();
+        assert!(text.contains("# HELP cache_hits_total Cache hits\n"));
+        assert!(text.contains(r#"cache_hits_total{key="a\"b\\c"} 1"#));
+    }
+}
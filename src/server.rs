@@ -2,12 +2,35 @@
 //!
 //! Core server implementation with generic context support.
 
+use crate::logging::LogConfig;
+use crate::metrics::{self, Metrics};
 use crate::pagination::{DEFAULT_PAGE_SIZE, PageState, paginate};
 use crate::transport::Transport;
 use crate::types::*;
 use serde_json::Value;
+use std::borrow::Cow;
 use std::collections::HashMap;
-use std::sync::{Arc, Mutex};
+use std::sync::{Arc, Mutex, OnceLock, RwLock};
+use std::time::Instant;
+
+#[cfg(feature = "auth")]
+use crate::auth::Claims;
+#[cfg(feature = "tracing")]
+use crate::telemetry::{self, RequestSpan};
+#[cfg(feature = "tracing")]
+use tracing::field::Empty;
+
+/// Request methods the server handles, used as metric labels
+const METHODS: &[&str] = &[
+    "initialize",
+    "ping",
+    "tools/list",
+    "tools/call",
+    "resources/list",
+    "resources/read",
+    "prompts/list",
+    "prompts/get",
+];
 
 //
 // Tool Environment - passed to tools during execution
@@ -18,7 +41,13 @@ use std::sync::{Arc, Mutex};
 /// Gives tools access to notifications, progress reporting, and resources.
 pub struct ToolEnv<'a> {
     transport: &'a Arc<Mutex<dyn Transport>>,
-    resources: &'a HashMap<String, Box<dyn Resource>>,
+    resources: &'a HashMap<String, Arc<dyn Resource>>,
+    session: &'a Session,
+    metrics: &'a Metrics,
+    #[cfg(feature = "auth")]
+    claims: Option<&'a Claims>,
+    #[cfg(feature = "tracing")]
+    span: tracing::Span,
 }
 
 impl<'a> ToolEnv<'a> {
@@ -55,14 +84,63 @@ impl<'a> ToolEnv<'a> {
         self.send_notification("notifications/progress", Some(params))
     }
 
-    /// List all resource URIs
+    /// List the URIs of resources the caller may read
     pub fn list_resources(&self) -> Vec<String> {
-        self.resources.keys().cloned().collect()
+        self.resources
+            .iter()
+            .filter(|(_, resource)| self.allows(resource.required_scopes()))
+            .map(|(uri, _)| uri.clone())
+            .collect()
     }
 
-    /// Get a resource by URI
+    /// Get a resource by URI, if the caller may read it
     pub fn get_resource(&self, uri: &str) -> Option<&dyn Resource> {
-        self.resources.get(uri).map(|r| r.as_ref())
+        self.resources
+            .get(uri)
+            .filter(|resource| self.allows(resource.required_scopes()))
+            .map(|r| r.as_ref())
+    }
+
+    /// True if the caller has the scopes for an item requiring `required`
+    fn allows(&self, required: &[&str]) -> bool {
+        #[cfg(feature = "auth")]
+        if let Some(claims) = self.claims {
+            return required.iter().all(|scope| claims.has_scope(scope));
+        }
+        let _ = required;
+        true
+    }
+
+    /// Name and version the client sent in `initialize`
+    pub fn client_info(&self) -> Option<&Implementation> {
+        self.session.client_info()
+    }
+
+    /// Capabilities the client declared in `initialize`
+    pub fn client_capabilities(&self) -> &ClientCapabilities {
+        self.session.client_capabilities()
+    }
+
+    /// Protocol version negotiated with the client
+    pub fn protocol_version(&self) -> Option<&str> {
+        self.session.protocol_version()
+    }
+
+    /// The server's metrics registry, for custom counters and histograms
+    pub fn metrics(&self) -> &Metrics {
+        self.metrics
+    }
+
+    /// Verified claims of the caller, when served with authentication
+    #[cfg(feature = "auth")]
+    pub fn claims(&self) -> Option<&Claims> {
+        self.claims
+    }
+
+    /// The `mcp.tool` span this call runs in, for creating child spans
+    #[cfg(feature = "tracing")]
+    pub fn span(&self) -> &tracing::Span {
+        &self.span
     }
 }
 
@@ -115,6 +193,14 @@ pub trait Tool<C>: Send + Sync {
         None
     }
 
+    /// Scopes a caller's token must carry to list and call this tool
+    ///
+    /// Only enforced for authenticated callers. Others don't see it listed,
+    /// and calls to it are refused with `insufficient_scope`. Default: none.
+    fn required_scopes(&self) -> &[&str] {
+        &[]
+    }
+
     /// Execute the tool
     fn execute(&self, args: Value, context: &mut C, env: &ToolEnv) -> Result<CallToolResult>;
 }
@@ -140,6 +226,14 @@ pub trait Resource: Send + Sync {
     /// Get resource content
     fn content(&self) -> Vec<ResourceContent>;
 
+    /// Scopes a caller's token must carry to list and read this resource
+    ///
+    /// Only enforced for authenticated callers. Others don't see it listed,
+    /// and reads of it are refused with `insufficient_scope`. Default: none.
+    fn required_scopes(&self) -> &[&str] {
+        &[]
+    }
+
     /// Convert to protocol Resource type
     fn as_protocol_resource(&self) -> crate::types::Resource {
         crate::types::Resource {
@@ -169,6 +263,14 @@ pub trait PromptDef: Send + Sync {
     /// Generate prompt messages
     fn get_messages(&self, args: &HashMap<String, String>) -> Result<Vec<PromptMessage>>;
 
+    /// Scopes a caller's token must carry to list and get this prompt
+    ///
+    /// Only enforced for authenticated callers. Others don't see it listed,
+    /// and requests for it are refused with `insufficient_scope`. Default: none.
+    fn required_scopes(&self) -> &[&str] {
+        &[]
+    }
+
     /// Convert to protocol Prompt type
     fn as_protocol_prompt(&self) -> Prompt {
         Prompt {
@@ -191,6 +293,17 @@ pub struct ServerConfig {
     pub instructions: Option<String>,
     /// Page size for list operations (tools, resources, prompts)
     pub page_size: usize,
+    /// Enforce the initialize lifecycle (default: true)
+    ///
+    /// When enabled, only `initialize` and `ping` are accepted before
+    /// initialization, and a second `initialize` is rejected.
+    pub strict_lifecycle: bool,
+    /// What gets logged (default: no message bodies)
+    pub logging: LogConfig,
+    /// Registry for request, error and tool latency metrics
+    ///
+    /// Clones share one registry, so keep a clone to render or scrape it.
+    pub metrics: Metrics,
 }
 
 impl Default for ServerConfig {
@@ -200,75 +313,365 @@ impl Default for ServerConfig {
             version: env!("CARGO_PKG_VERSION").to_string(),
             instructions: None,
             page_size: DEFAULT_PAGE_SIZE,
+            strict_lifecycle: true,
+            logging: LogConfig::default(),
+            metrics: Metrics::default(),
         }
     }
 }
 
 //
-// MCP Server
+// Session state
 //
 
-/// MCP Server - generic over context type
-pub struct Server<C> {
-    config: ServerConfig,
-    tools: HashMap<String, Box<dyn Tool<C>>>,
-    resources: HashMap<String, Box<dyn Resource>>,
-    prompts: HashMap<String, Box<dyn PromptDef>>,
-    transport: Option<Arc<Mutex<dyn Transport>>>,
-    initialized: bool,
+/// Lifecycle phase of an MCP session
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum LifecycleState {
+    /// Waiting for the `initialize` request
+    #[default]
+    Uninitialized,
+    /// `initialize` answered, waiting for `notifications/initialized`
+    Initializing,
+    /// Client sent `notifications/initialized`, normal operation
+    Ready,
 }
 
-impl<C: Send + Sync + 'static> Server<C> {
-    /// Create a new server with the given configuration
-    pub fn new(config: ServerConfig) -> Self {
+/// Per-client session state
+///
+/// The stdio server keeps a single session for the lifetime of the process.
+/// `HttpServer` keeps one per `Mcp-Session-Id`.
+#[derive(Debug, Clone, Default)]
+pub struct Session {
+    state: LifecycleState,
+    client_info: Option<Implementation>,
+    client_capabilities: ClientCapabilities,
+    protocol_version: Option<String>,
+}
+
+impl Session {
+    /// Current lifecycle phase
+    pub fn state(&self) -> LifecycleState {
+        self.state
+    }
+
+    /// True once `initialize` has been answered
+    pub fn is_initialized(&self) -> bool {
+        self.state != LifecycleState::Uninitialized
+    }
+
+    /// Client name and version from `initialize`
+    pub fn client_info(&self) -> Option<&Implementation> {
+        self.client_info.as_ref()
+    }
+
+    /// Client capabilities from `initialize` (empty before initialization)
+    pub fn client_capabilities(&self) -> &ClientCapabilities {
+        &self.client_capabilities
+    }
+
+    /// Negotiated protocol version, once initialized
+    pub fn protocol_version(&self) -> Option<&str> {
+        self.protocol_version.as_deref()
+    }
+}
+
+//
+// Registry - tools, resources and prompts, shared between servers
+//
+
+/// The tools, resources and prompts a server offers
+///
+/// Built once and shared through an `Arc`, so a server per request or
+/// session costs no re-registration. `Server::add_*` copies a shared
+/// registry before changing it. Listings are built on first use and cached.
+pub struct Registry<C> {
+    tools: HashMap<String, Arc<dyn Tool<C>>>,
+    resources: HashMap<String, Arc<dyn Resource>>,
+    prompts: HashMap<String, Arc<dyn PromptDef>>,
+    tool_list: OnceLock<Vec<crate::types::Tool>>,
+    resource_list: OnceLock<Vec<crate::types::Resource>>,
+    prompt_list: OnceLock<Vec<Prompt>>,
+}
+
+impl<C> Default for Registry<C> {
+    fn default() -> Self {
         Self {
-            config,
             tools: HashMap::new(),
             resources: HashMap::new(),
             prompts: HashMap::new(),
-            transport: None,
-            initialized: false,
+            tool_list: OnceLock::new(),
+            resource_list: OnceLock::new(),
+            prompt_list: OnceLock::new(),
         }
     }
+}
 
-    /// Add a tool to the server
+impl<C> Clone for Registry<C> {
+    fn clone(&self) -> Self {
+        Self {
+            tools: self.tools.clone(),
+            resources: self.resources.clone(),
+            prompts: self.prompts.clone(),
+            tool_list: self.tool_list.clone(),
+            resource_list: self.resource_list.clone(),
+            prompt_list: self.prompt_list.clone(),
+        }
+    }
+}
+
+impl<C: Send + Sync + 'static> Registry<C> {
+    /// Create an empty registry
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Add a tool
     pub fn add_tool(&mut self, tool: impl Tool<C> + 'static) -> Result<()> {
         let name = tool.name().to_string();
         if self.tools.contains_key(&name) {
             return Err(McpError::Internal(format!("Duplicate tool: {}", name)));
         }
-        self.tools.insert(name, Box::new(tool));
+        self.tools.insert(name, Arc::new(tool));
+        self.tool_list = OnceLock::new();
         Ok(())
     }
 
-    /// Add a resource to the server
+    /// Add a resource
     pub fn add_resource(&mut self, resource: impl Resource + 'static) -> Result<()> {
         let uri = resource.uri();
         if self.resources.contains_key(&uri) {
             return Err(McpError::Internal(format!("Duplicate resource: {}", uri)));
         }
-        self.resources.insert(uri, Box::new(resource));
+        self.resources.insert(uri, Arc::new(resource));
+        self.resource_list = OnceLock::new();
         Ok(())
     }
 
-    /// Add a prompt to the server
+    /// Add a prompt
     pub fn add_prompt(&mut self, prompt: impl PromptDef + 'static) -> Result<()> {
         let name = prompt.name().to_string();
         if self.prompts.contains_key(&name) {
             return Err(McpError::Internal(format!("Duplicate prompt: {}", name)));
         }
-        self.prompts.insert(name, Box::new(prompt));
+        self.prompts.insert(name, Arc::new(prompt));
+        self.prompt_list = OnceLock::new();
         Ok(())
     }
 
+    /// Remove a tool, returning true if it was registered
+    pub fn remove_tool(&mut self, name: &str) -> bool {
+        self.tool_list = OnceLock::new();
+        self.tools.remove(name).is_some()
+    }
+
+    /// Remove a resource, returning true if it was registered
+    pub fn remove_resource(&mut self, uri: &str) -> bool {
+        self.resource_list = OnceLock::new();
+        self.resources.remove(uri).is_some()
+    }
+
+    /// Remove a prompt, returning true if it was registered
+    pub fn remove_prompt(&mut self, name: &str) -> bool {
+        self.prompt_list = OnceLock::new();
+        self.prompts.remove(name).is_some()
+    }
+
+    /// Tools as listed by `tools/list`, sorted by name
+    fn tool_list(&self) -> &[crate::types::Tool] {
+        self.tool_list.get_or_init(|| {
+            let mut tools: Vec<crate::types::Tool> = self
+                .tools
+                .values()
+                .map(|t| crate::types::Tool {
+                    name: t.name().to_string(),
+                    description: Some(t.description().to_string()),
+                    input_schema: t.schema(),
+                    annotations: t.annotations(),
+                })
+                .collect();
+            tools.sort_by(|a, b| a.name.cmp(&b.name));
+            tools
+        })
+    }
+
+    /// Resources as listed by `resources/list`, sorted by URI
+    fn resource_list(&self) -> &[crate::types::Resource] {
+        self.resource_list.get_or_init(|| {
+            let mut resources: Vec<crate::types::Resource> = self
+                .resources
+                .values()
+                .map(|r| r.as_protocol_resource())
+                .collect();
+            resources.sort_by(|a, b| a.uri.cmp(&b.uri));
+            resources
+        })
+    }
+
+    /// Prompts as listed by `prompts/list`, sorted by name
+    fn prompt_list(&self) -> &[Prompt] {
+        self.prompt_list.get_or_init(|| {
+            let mut prompts: Vec<Prompt> = self
+                .prompts
+                .values()
+                .map(|p| p.as_protocol_prompt())
+                .collect();
+            prompts.sort_by(|a, b| a.name.cmp(&b.name));
+            prompts
+        })
+    }
+}
+
+/// Shared handle to a registry that can change while serving
+///
+/// Each request takes a snapshot; `update` edits a copy and swaps it in,
+/// so requests already running keep the registry they started with.
+///
+/// # Example
+/// ```ignore
+/// let registry = http_server.registry();
+/// // later, from any thread
+/// registry.update(|r| r.add_tool(NewTool))?;
+/// ```
+pub struct RegistryHandle<C> {
+    current: Arc<RwLock<Arc<Registry<C>>>>,
+}
+
+impl<C> Clone for RegistryHandle<C> {
+    fn clone(&self) -> Self {
+        Self {
+            current: self.current.clone(),
+        }
+    }
+}
+
+impl<C> Default for RegistryHandle<C> {
+    fn default() -> Self {
+        Self {
+            current: Arc::new(RwLock::new(Arc::new(Registry::default()))),
+        }
+    }
+}
+
+impl<C: Send + Sync + 'static> RegistryHandle<C> {
+    /// The registry as it is now
+    pub fn snapshot(&self) -> Arc<Registry<C>> {
+        self.current
+            .read()
+            .unwrap_or_else(|e| e.into_inner())
+            .clone()
+    }
+
+    /// Change the registry for all later requests
+    pub fn update<R>(&self, change: impl FnOnce(&mut Registry<C>) -> R) -> R {
+        let mut current = self.current.write().unwrap_or_else(|e| e.into_inner());
+        change(Arc::make_mut(&mut current))
+    }
+
+    /// Swap in a whole registry
+    pub fn replace(&self, registry: Arc<Registry<C>>) {
+        *self.current.write().unwrap_or_else(|e| e.into_inner()) = registry;
+    }
+}
+
+//
+// MCP Server
+//
+
+/// MCP Server - generic over context type
+pub struct Server<C> {
+    config: ServerConfig,
+    registry: Arc<Registry<C>>,
+    transport: Option<Arc<Mutex<dyn Transport>>>,
+    session: Session,
+    #[cfg(feature = "auth")]
+    claims: Option<Claims>,
+    /// Scopes of the last request refused for lacking them
+    #[cfg(feature = "auth")]
+    denied_scope: Option<String>,
+}
+
+impl<C: Send + Sync + 'static> Server<C> {
+    /// Create a new server with the given configuration
+    pub fn new(config: ServerConfig) -> Self {
+        Self::with_registry(config, Arc::new(Registry::default()))
+    }
+
+    /// Create a server offering an existing, possibly shared, registry
+    pub fn with_registry(config: ServerConfig, registry: Arc<Registry<C>>) -> Self {
+        Self {
+            config,
+            registry,
+            transport: None,
+            session: Session::default(),
+            #[cfg(feature = "auth")]
+            claims: None,
+            #[cfg(feature = "auth")]
+            denied_scope: None,
+        }
+    }
+
+    /// The tools, resources and prompts this server offers
+    pub fn registry(&self) -> &Arc<Registry<C>> {
+        &self.registry
+    }
+
+    /// Add a tool to the server
+    pub fn add_tool(&mut self, tool: impl Tool<C> + 'static) -> Result<()> {
+        Arc::make_mut(&mut self.registry).add_tool(tool)
+    }
+
+    /// Add a resource to the server
+    pub fn add_resource(&mut self, resource: impl Resource + 'static) -> Result<()> {
+        Arc::make_mut(&mut self.registry).add_resource(resource)
+    }
+
+    /// Add a prompt to the server
+    pub fn add_prompt(&mut self, prompt: impl PromptDef + 'static) -> Result<()> {
+        Arc::make_mut(&mut self.registry).add_prompt(prompt)
+    }
+
+    /// Set the verified claims of the caller
+    ///
+    /// Tools, resources and prompts with `required_scopes` are then hidden
+    /// from lists and refused unless the claims carry every scope. Without
+    /// claims nothing is restricted.
+    #[cfg(feature = "auth")]
+    pub fn set_claims(&mut self, claims: Option<Claims>) {
+        self.claims = claims;
+    }
+
+    /// Verified claims of the caller, if any
+    #[cfg(feature = "auth")]
+    pub fn claims(&self) -> Option<&Claims> {
+        self.claims.as_ref()
+    }
+
+    /// Take the scopes of a request refused for lacking them, if any
+    #[cfg(feature = "auth")]
+    #[cfg_attr(not(feature = "http"), allow(dead_code))]
+    pub(crate) fn take_denied_scope(&mut self) -> Option<String> {
+        self.denied_scope.take()
+    }
+
+    /// Current session state
+    pub fn session(&self) -> &Session {
+        &self.session
+    }
+
+    /// Replace the session state (used by transports that multiplex sessions)
+    pub fn set_session(&mut self, session: Session) {
+        self.session = session;
+    }
+
     /// Run the server with the given transport and context (for stdio - continuous loop)
     pub fn start<T: Transport + 'static>(&mut self, transport: T, mut context: C) -> Result<()> {
         let transport: Arc<Mutex<dyn Transport>> = Arc::new(Mutex::new(transport));
         self.transport = Some(transport.clone());
 
-        eprintln!(
+        log::info!(
             "MCP Server `{}` started, version {}",
-            self.config.name, self.config.version
+            self.config.name,
+            self.config.version
         );
 
         loop {
@@ -280,6 +683,19 @@ impl<C: Send + Sync + 'static> Server<C> {
                 match t.read() {
                     Ok(msg) => msg,
                     Err(McpError::TransportClosed) => break,
+                    // Malformed input - tell the client and keep the session alive
+                    Err(
+                        e @ (McpError::Json(_)
+                        | McpError::InvalidMessage(_)
+                        | McpError::MessageTooLarge(_)),
+                    ) => {
+                        log::warn!("Rejected message: {}", e);
+                        t.write(&JsonRpcMessage::error(
+                            RequestId::Null,
+                            e.to_jsonrpc_error(),
+                        ))?;
+                        continue;
+                    }
                     Err(e) => return Err(e),
                 }
             };
@@ -338,10 +754,13 @@ impl<C: Send + Sync + 'static> Server<C> {
     ) -> Result<Option<JsonRpcMessage>> {
         match message {
             JsonRpcMessage::Request(request) => {
-                let response = self.handle_request(request, context);
+                self.config.logging.request(&request);
+                let response = self.handle_request(&request, context);
+                self.config.logging.response(&request, &response);
                 Ok(Some(response))
             }
             JsonRpcMessage::Notification(notification) => {
+                self.config.logging.notification(&notification);
                 self.handle_notification(notification)?;
                 Ok(None)
             }
@@ -350,16 +769,103 @@ impl<C: Send + Sync + 'static> Server<C> {
     }
 
     /// Handle a request and return a response
-    fn handle_request(&mut self, request: JsonRpcRequest, context: &mut C) -> JsonRpcMessage {
+    fn handle_request(&mut self, request: &JsonRpcRequest, context: &mut C) -> JsonRpcMessage {
         let id = request.id.clone();
 
-        match self.dispatch_request(&request, context) {
+        #[cfg(feature = "tracing")]
+        let span = RequestSpan::new(request);
+        #[cfg(feature = "tracing")]
+        let entered = span.span().clone().entered();
+
+        let result = self
+            .check_lifecycle(&request.method)
+            .and_then(|_| self.dispatch_request(request, context));
+
+        #[cfg(feature = "tracing")]
+        {
+            drop(entered);
+            span.finish(&result);
+        }
+
+        self.record_request(&request.method, &result);
+        #[cfg(feature = "auth")]
+        if let Err(McpError::InsufficientScope(scope)) = &result {
+            self.denied_scope = Some(scope.clone());
+        }
+
+        match result {
             Ok(result) => JsonRpcMessage::response(id, result),
             Err(e) => JsonRpcMessage::error(id, e.to_jsonrpc_error()),
         }
     }
 
+    /// Count the request and, if it failed, its JSON-RPC error code
+    fn record_request(&self, method: &str, result: &Result<Value>) {
+        let registry = &self.config.metrics;
+        // Anything but a known method shares one label to keep cardinality bounded
+        let method = METHODS
+            .iter()
+            .find(|known| **known == method)
+            .copied()
+            .unwrap_or("other");
+        registry.inc(metrics::REQUESTS_TOTAL, &[("method", method)]);
+        if let Err(e) = result {
+            let code = e.to_jsonrpc_error().code.to_string();
+            registry.inc(metrics::ERRORS_TOTAL, &[("code", &code)]);
+        }
+    }
+
+    /// Reject requests that arrive out of order for the session lifecycle
+    fn check_lifecycle(&self, method: &str) -> Result<()> {
+        if !self.config.strict_lifecycle {
+            return Ok(());
+        }
+
+        match (method, self.session.state) {
+            ("ping", _) => Ok(()),
+            ("initialize", LifecycleState::Uninitialized) => Ok(()),
+            ("initialize", _) => Err(McpError::AlreadyInitialized),
+            (_, LifecycleState::Uninitialized) => Err(McpError::NotInitialized),
+            _ => Ok(()),
+        }
+    }
+
+    /// True if the caller may use an item requiring `required` scopes
+    fn allows(&self, required: &[&str]) -> bool {
+        #[cfg(feature = "auth")]
+        if let Some(claims) = &self.claims {
+            return required.iter().all(|scope| claims.has_scope(scope));
+        }
+        let _ = required;
+        true
+    }
+
+    /// Refuse an item the caller lacks scopes for
+    fn check_scopes(&self, required: &[&str]) -> Result<()> {
+        #[cfg(feature = "auth")]
+        if !self.allows(required) {
+            return Err(McpError::InsufficientScope(required.join(" ")));
+        }
+        let _ = required;
+        Ok(())
+    }
+
+    /// The listed items the caller has scopes for
+    fn visible<'a, T: Clone>(
+        &'a self,
+        items: &'a [T],
+        required: impl Fn(&T) -> &'a [&'a str],
+    ) -> Cow<'a, [T]> {
+        if items.iter().all(|item| self.allows(required(item))) {
+            return Cow::Borrowed(items);
+        }
+        let visible = items.iter().filter(|item| self.allows(required(item)));
+        Cow::Owned(visible.cloned().collect())
+    }
+
     /// Dispatch a request to the appropriate handler
+    ///
+    /// Keep `METHODS` in step with the methods handled here.
     fn dispatch_request(&mut self, request: &JsonRpcRequest, context: &mut C) -> Result<Value> {
         match request.method.as_str() {
             "initialize" => self.handle_initialize(request),
@@ -375,27 +881,32 @@ impl<C: Send + Sync + 'static> Server<C> {
     }
 
     fn handle_initialize(&mut self, request: &JsonRpcRequest) -> Result<Value> {
-        let _params: InitializeParams = match &request.params {
+        let params: InitializeParams = match &request.params {
             Some(p) => serde_json::from_value(p.clone())?,
             None => InitializeParams::default(),
         };
 
-        self.initialized = true;
+        let protocol_version = negotiate_protocol_version(&params.protocol_version);
+
+        self.session.state = LifecycleState::Initializing;
+        self.session.client_info = Some(params.client_info);
+        self.session.client_capabilities = params.capabilities;
+        self.session.protocol_version = Some(protocol_version.to_string());
 
         let result = InitializeResult {
-            protocol_version: PROTOCOL_VERSION.to_string(),
+            protocol_version: protocol_version.to_string(),
             capabilities: ServerCapabilities {
-                tools: if self.tools.is_empty() {
+                tools: if self.registry.tools.is_empty() {
                     None
                 } else {
                     Some(ToolsCapability::default())
                 },
-                resources: if self.resources.is_empty() {
+                resources: if self.registry.resources.is_empty() {
                     None
                 } else {
                     Some(ResourcesCapability::default())
                 },
-                prompts: if self.prompts.is_empty() {
+                prompts: if self.registry.prompts.is_empty() {
                     None
                 } else {
                     Some(PromptsCapability::default())
@@ -423,22 +934,12 @@ impl<C: Send + Sync + 'static> Server<C> {
             None => ListToolsParams::default(),
         };
 
-        // Collect all tools (sorted for consistent pagination)
-        let mut all_tools: Vec<crate::types::Tool> = self
-            .tools
-            .values()
-            .map(|t| crate::types::Tool {
-                name: t.name().to_string(),
-                description: Some(t.description().to_string()),
-                input_schema: t.schema(),
-                annotations: t.annotations(),
-            })
-            .collect();
-        all_tools.sort_by(|a, b| a.name.cmp(&b.name));
-
-        // Apply pagination
+        // Sorted for consistent pagination
         let state = PageState::from_cursor(params.cursor.as_deref(), self.config.page_size);
-        let (tools, next_cursor) = paginate(&all_tools, &state);
+        let tools = self.visible(self.registry.tool_list(), |tool| {
+            self.registry.tools[&tool.name].required_scopes()
+        });
+        let (tools, next_cursor) = paginate(&tools, &state);
 
         Ok(serde_json::to_value(ListToolsResult {
             tools,
@@ -453,20 +954,42 @@ impl<C: Send + Sync + 'static> Server<C> {
         };
 
         let tool = self
+            .registry
             .tools
             .get(&params.name)
             .ok_or_else(|| McpError::ToolError(format!("Unknown tool: {}", params.name)))?;
+        self.check_scopes(tool.required_scopes())?;
+
+        #[cfg(feature = "tracing")]
+        let span = tracing::info_span!("mcp.tool", tool = params.name.as_str(), outcome = Empty);
+        #[cfg(feature = "tracing")]
+        let _entered = span.clone().entered();
 
         let env = ToolEnv {
             transport: self.transport.as_ref().unwrap(),
-            resources: &self.resources,
+            resources: &self.registry.resources,
+            session: &self.session,
+            metrics: &self.config.metrics,
+            #[cfg(feature = "auth")]
+            claims: self.claims.as_ref(),
+            #[cfg(feature = "tracing")]
+            span: span.clone(),
         };
 
+        let started = Instant::now();
         let result = tool.execute(
             params.arguments.unwrap_or(serde_json::json!({})),
             context,
             &env,
-        )?;
+        );
+        self.config.metrics.observe(
+            metrics::TOOL_DURATION_SECONDS,
+            &[("tool", &params.name)],
+            started.elapsed().as_secs_f64(),
+        );
+        #[cfg(feature = "tracing")]
+        telemetry::record_outcome(&span, &result);
+        let result = result?;
 
         Ok(serde_json::to_value(result)?)
     }
@@ -477,17 +1000,12 @@ impl<C: Send + Sync + 'static> Server<C> {
             None => ListResourcesParams::default(),
         };
 
-        // Collect all resources (sorted for consistent pagination)
-        let mut all_resources: Vec<crate::types::Resource> = self
-            .resources
-            .values()
-            .map(|r| r.as_protocol_resource())
-            .collect();
-        all_resources.sort_by(|a, b| a.uri.cmp(&b.uri));
-
-        // Apply pagination
+        // Sorted for consistent pagination
         let state = PageState::from_cursor(params.cursor.as_deref(), self.config.page_size);
-        let (resources, next_cursor) = paginate(&all_resources, &state);
+        let resources = self.visible(self.registry.resource_list(), |resource| {
+            self.registry.resources[&resource.uri].required_scopes()
+        });
+        let (resources, next_cursor) = paginate(&resources, &state);
 
         Ok(serde_json::to_value(ListResourcesResult {
             resources,
@@ -502,9 +1020,14 @@ impl<C: Send + Sync + 'static> Server<C> {
         };
 
         let resource = self
+            .registry
             .resources
             .get(&params.uri)
             .ok_or_else(|| McpError::ResourceNotFound(params.uri.clone()))?;
+        self.check_scopes(resource.required_scopes())?;
+
+        #[cfg(feature = "tracing")]
+        let _span = tracing::info_span!("mcp.resource", uri = params.uri.as_str()).entered();
 
         let contents = resource.content();
         Ok(serde_json::to_value(ReadResourceResult { contents })?)
@@ -516,17 +1039,12 @@ impl<C: Send + Sync + 'static> Server<C> {
             None => ListPromptsParams::default(),
         };
 
-        // Collect all prompts (sorted for consistent pagination)
-        let mut all_prompts: Vec<Prompt> = self
-            .prompts
-            .values()
-            .map(|p| p.as_protocol_prompt())
-            .collect();
-        all_prompts.sort_by(|a, b| a.name.cmp(&b.name));
-
-        // Apply pagination
+        // Sorted for consistent pagination
         let state = PageState::from_cursor(params.cursor.as_deref(), self.config.page_size);
-        let (prompts, next_cursor) = paginate(&all_prompts, &state);
+        let prompts = self.visible(self.registry.prompt_list(), |prompt| {
+            self.registry.prompts[&prompt.name].required_scopes()
+        });
+        let (prompts, next_cursor) = paginate(&prompts, &state);
 
         Ok(serde_json::to_value(ListPromptsResult {
             prompts,
@@ -541,9 +1059,14 @@ impl<C: Send + Sync + 'static> Server<C> {
         };
 
         let prompt = self
+            .registry
             .prompts
             .get(&params.name)
             .ok_or_else(|| McpError::PromptNotFound(params.name.clone()))?;
+        self.check_scopes(prompt.required_scopes())?;
+
+        #[cfg(feature = "tracing")]
+        let _span = tracing::info_span!("mcp.prompt", prompt = params.name.as_str()).entered();
 
         let messages = prompt.get_messages(&params.arguments)?;
         let result = GetPromptResult {
@@ -556,7 +1079,13 @@ impl<C: Send + Sync + 'static> Server<C> {
 
     fn handle_notification(&mut self, notification: JsonRpcNotification) -> Result<()> {
         match notification.method.as_str() {
-            "notifications/initialized" => Ok(()),
+            "notifications/initialized" => {
+                // Only meaningful after `initialize`; notifications can't be answered
+                if self.session.state == LifecycleState::Initializing {
+                    self.session.state = LifecycleState::Ready;
+                }
+                Ok(())
+            }
             "notifications/cancelled" => Ok(()),
             _ => Ok(()),
         }
@@ -772,7 +1301,7 @@ mod tests {
     fn test_add_tool() {
         let mut server: Server<TestContext> = Server::new(ServerConfig::default());
         server.add_tool(IncrementTool).unwrap();
-        assert!(server.tools.contains_key("increment"));
+        assert!(server.registry.tools.contains_key("increment"));
     }
 
     #[test]
@@ -791,7 +1320,7 @@ mod tests {
             data: "hello".into(),
         };
         server.add_resource(resource).unwrap();
-        assert!(server.resources.contains_key("test://data"));
+        assert!(server.registry.resources.contains_key("test://data"));
     }
 
     #[test]
@@ -814,7 +1343,7 @@ mod tests {
     fn test_add_prompt() {
         let mut server: Server<TestContext> = Server::new(ServerConfig::default());
         server.add_prompt(TestPrompt).unwrap();
-        assert!(server.prompts.contains_key("test-prompt"));
+        assert!(server.registry.prompts.contains_key("test-prompt"));
     }
 
     #[test]
@@ -867,7 +1396,7 @@ mod tests {
 
         let value = result.unwrap();
         assert_eq!(value["serverInfo"]["name"], "test-server");
-        assert!(server.initialized);
+        assert!(server.session.is_initialized());
     }
 
     #[test]
@@ -1236,7 +1765,7 @@ mod tests {
             params: None,
         };
         let mut ctx = TestContext { counter: 0 };
-        let response = server.handle_request(request, &mut ctx);
+        let response = server.handle_request(&request, &mut ctx);
 
         if let JsonRpcMessage::Response(resp) = response {
             assert!(resp.error.is_some());
@@ -1251,25 +1780,29 @@ mod tests {
         let mut server: Server<TestContext> = Server::new(ServerConfig::default());
         server.add_tool(IncrementTool).unwrap();
 
-        let messages = vec![make_request(
-            1,
-            "tools/call",
-            Some(serde_json::json!({
-                "name": "increment",
-                "arguments": { "amount": 10 }
-            })),
-        )];
+        let messages = vec![
+            make_request(1, "initialize", None),
+            make_request(
+                2,
+                "tools/call",
+                Some(serde_json::json!({
+                    "name": "increment",
+                    "arguments": { "amount": 10 }
+                })),
+            ),
+        ];
 
         let transport = Arc::new(Mutex::new(MockTransport::new(messages)));
         let mut ctx = TestContext { counter: 0 };
 
+        server.process_one(transport.clone(), &mut ctx).unwrap();
         server.process_one(transport.clone(), &mut ctx).unwrap();
 
         assert_eq!(ctx.counter, 10);
 
         let t = transport.lock().unwrap();
         let responses = t.get_responses();
-        assert_eq!(responses.len(), 1);
+        assert_eq!(responses.len(), 2);
     }
 
     #[test]
@@ -1319,26 +1852,34 @@ mod tests {
 
     #[test]
     fn test_tool_env_list_resources() {
-        let mut resources: HashMap<String, Box<dyn Resource>> = HashMap::new();
+        let mut resources: HashMap<String, Arc<dyn Resource>> = HashMap::new();
         resources.insert(
             "test://a".into(),
-            Box::new(TestResource {
+            Arc::new(TestResource {
                 uri: "test://a".into(),
                 data: "a".into(),
             }),
         );
         resources.insert(
             "test://b".into(),
-            Box::new(TestResource {
+            Arc::new(TestResource {
                 uri: "test://b".into(),
                 data: "b".into(),
             }),
         );
 
         let transport: Arc<Mutex<dyn Transport>> = Arc::new(Mutex::new(MockTransport::new(vec![])));
+        let session = Session::default();
+        let metrics = Metrics::new();
         let env = ToolEnv {
             transport: &transport,
             resources: &resources,
+            session: &session,
+            metrics: &metrics,
+            #[cfg(feature = "auth")]
+            claims: None,
+            #[cfg(feature = "tracing")]
+            span: tracing::Span::none(),
         };
 
         let uris = env.list_resources();
@@ -1349,19 +1890,27 @@ mod tests {
 
     #[test]
     fn test_tool_env_get_resource() {
-        let mut resources: HashMap<String, Box<dyn Resource>> = HashMap::new();
+        let mut resources: HashMap<String, Arc<dyn Resource>> = HashMap::new();
         resources.insert(
             "test://data".into(),
-            Box::new(TestResource {
+            Arc::new(TestResource {
                 uri: "test://data".into(),
                 data: "hello".into(),
             }),
         );
 
         let transport: Arc<Mutex<dyn Transport>> = Arc::new(Mutex::new(MockTransport::new(vec![])));
+        let session = Session::default();
+        let metrics = Metrics::new();
         let env = ToolEnv {
             transport: &transport,
             resources: &resources,
+            session: &session,
+            metrics: &metrics,
+            #[cfg(feature = "auth")]
+            claims: None,
+            #[cfg(feature = "tracing")]
+            span: tracing::Span::none(),
         };
 
         let found = env.get_resource("test://data");
@@ -1377,15 +1926,19 @@ mod tests {
         let mut server: Server<TestContext> = Server::new(ServerConfig::default());
         server.add_tool(NotifyTool).unwrap();
 
-        let messages = vec![make_request(
-            1,
-            "tools/call",
-            Some(serde_json::json!({ "name": "notify" })),
-        )];
+        let messages = vec![
+            make_request(1, "initialize", None),
+            make_request(
+                2,
+                "tools/call",
+                Some(serde_json::json!({ "name": "notify" })),
+            ),
+        ];
 
         let transport = Arc::new(Mutex::new(MockTransport::new(messages)));
         let mut ctx = TestContext { counter: 0 };
 
+        server.process_one(transport.clone(), &mut ctx).unwrap();
         server.process_one(transport.clone(), &mut ctx).unwrap();
 
         let t = transport.lock().unwrap();
@@ -1567,4 +2120,883 @@ mod tests {
         let tools = result["tools"].as_array().unwrap();
         assert_eq!(tools.len(), 1); // Should get the one tool
     }
+
+    // Helper to pull the error code out of a response message
+    fn error_code(message: &JsonRpcMessage) -> Option<i32> {
+        match message {
+            JsonRpcMessage::Response(resp) => resp.error.as_ref().map(|e| e.code),
+            _ => None,
+        }
+    }
+
+    #[test]
+    fn test_lifecycle_rejects_requests_before_initialize() {
+        let mut server: Server<TestContext> = Server::new(ServerConfig::default());
+        server.add_tool(IncrementTool).unwrap();
+        let mut ctx = TestContext { counter: 0 };
+
+        let call = make_request(
+            1,
+            "tools/call",
+            Some(serde_json::json!({ "name": "increment" })),
+        );
+        let response = server.handle_message(call, &mut ctx).unwrap().unwrap();
+        assert_eq!(error_code(&response), Some(-32600));
+        assert_eq!(ctx.counter, 0);
+
+        let list = make_request(2, "tools/list", None);
+        let response = server.handle_message(list, &mut ctx).unwrap().unwrap();
+        assert_eq!(error_code(&response), Some(-32600));
+
+        // Ping is always allowed
+        let ping = make_request(3, "ping", None);
+        let response = server.handle_message(ping, &mut ctx).unwrap().unwrap();
+        assert_eq!(error_code(&response), None);
+    }
+
+    #[test]
+    fn test_lifecycle_state_transitions() {
+        let mut server: Server<TestContext> = Server::new(ServerConfig::default());
+        let mut ctx = TestContext { counter: 0 };
+        assert_eq!(server.session().state(), LifecycleState::Uninitialized);
+
+        // Initialized notification before initialize is ignored
+        let notification = make_notification("notifications/initialized", None);
+        server.handle_message(notification, &mut ctx).unwrap();
+        assert_eq!(server.session().state(), LifecycleState::Uninitialized);
+
+        let init = make_request(1, "initialize", None);
+        let response = server.handle_message(init, &mut ctx).unwrap().unwrap();
+        assert_eq!(error_code(&response), None);
+        assert_eq!(server.session().state(), LifecycleState::Initializing);
+
+        // Requests are allowed once initialize has been answered
+        let list = make_request(2, "tools/list", None);
+        let response = server.handle_message(list, &mut ctx).unwrap().unwrap();
+        assert_eq!(error_code(&response), None);
+
+        let notification = make_notification("notifications/initialized", None);
+        server.handle_message(notification, &mut ctx).unwrap();
+        assert_eq!(server.session().state(), LifecycleState::Ready);
+    }
+
+    #[test]
+    fn test_lifecycle_rejects_duplicate_initialize() {
+        let mut server: Server<TestContext> = Server::new(ServerConfig::default());
+        let mut ctx = TestContext { counter: 0 };
+
+        let init = make_request(1, "initialize", None);
+        server.handle_message(init, &mut ctx).unwrap();
+
+        let again = make_request(2, "initialize", None);
+        let response = server.handle_message(again, &mut ctx).unwrap().unwrap();
+        assert_eq!(error_code(&response), Some(-32600));
+    }
+
+    #[test]
+    fn test_lifecycle_not_enforced_when_disabled() {
+        let mut server: Server<TestContext> = Server::new(ServerConfig {
+            strict_lifecycle: false,
+            ..Default::default()
+        });
+        server.add_tool(IncrementTool).unwrap();
+        let transport: Arc<Mutex<dyn Transport>> = Arc::new(Mutex::new(MockTransport::new(vec![])));
+        server.transport = Some(transport);
+        let mut ctx = TestContext { counter: 0 };
+
+        let call = make_request(
+            1,
+            "tools/call",
+            Some(serde_json::json!({ "name": "increment" })),
+        );
+        let response = server.handle_message(call, &mut ctx).unwrap().unwrap();
+        assert_eq!(error_code(&response), None);
+        assert_eq!(ctx.counter, 1);
+
+        // Repeated initialize is accepted
+        for id in 2..4 {
+            let init = make_request(id, "initialize", None);
+            let response = server.handle_message(init, &mut ctx).unwrap().unwrap();
+            assert_eq!(error_code(&response), None);
+        }
+    }
+
+    #[test]
+    fn test_set_session_restores_state() {
+        let mut server: Server<TestContext> = Server::new(ServerConfig::default());
+        let mut ctx = TestContext { counter: 0 };
+
+        let init = make_request(1, "initialize", None);
+        server.handle_message(init, &mut ctx).unwrap();
+        let session = server.session().clone();
+
+        let mut other: Server<TestContext> = Server::new(ServerConfig::default());
+        other.set_session(session);
+        let list = make_request(2, "tools/list", None);
+        let response = other.handle_message(list, &mut ctx).unwrap().unwrap();
+        assert_eq!(error_code(&response), None);
+    }
+
+    // Tool that reports what it knows about the client
+    struct ClientInfoTool;
+
+    impl Tool<TestContext> for ClientInfoTool {
+        fn name(&self) -> &str {
+            "client_info"
+        }
+        fn description(&self) -> &str {
+            "Describe the client"
+        }
+        fn schema(&self) -> Value {
+            serde_json::json!({"type": "object"})
+        }
+        fn execute(
+            &self,
+            _args: Value,
+            _ctx: &mut TestContext,
+            env: &ToolEnv,
+        ) -> Result<CallToolResult> {
+            let name = env.client_info().map(|i| i.name.as_str()).unwrap_or("?");
+            Ok(CallToolResult::text(format!(
+                "{} sampling={} version={}",
+                name,
+                env.client_capabilities().supports_sampling(),
+                env.protocol_version().unwrap_or("?")
+            )))
+        }
+    }
+
+    #[test]
+    fn test_initialize_stores_client_info() {
+        let mut server: Server<TestContext> = Server::new(ServerConfig::default());
+        let mut ctx = TestContext { counter: 0 };
+        assert!(server.session().client_info().is_none());
+
+        let init = make_request(
+            1,
+            "initialize",
+            Some(serde_json::json!({
+                "protocolVersion": "2024-11-05",
+                "capabilities": { "sampling": {} },
+                "clientInfo": { "name": "claude-ai", "version": "0.1.0" }
+            })),
+        );
+        let response = server.handle_message(init, &mut ctx).unwrap().unwrap();
+
+        // Supported version is echoed back
+        if let JsonRpcMessage::Response(resp) = response {
+            assert_eq!(resp.result.unwrap()["protocolVersion"], "2024-11-05");
+        } else {
+            panic!("Expected response");
+        }
+
+        let session = server.session();
+        assert_eq!(session.client_info().unwrap().name, "claude-ai");
+        assert_eq!(session.client_info().unwrap().version, "0.1.0");
+        assert!(session.client_capabilities().supports_sampling());
+        assert_eq!(session.protocol_version(), Some("2024-11-05"));
+    }
+
+    #[test]
+    fn test_initialize_unknown_protocol_version() {
+        let mut server: Server<TestContext> = Server::new(ServerConfig::default());
+        let request = JsonRpcRequest {
+            jsonrpc: Default::default(),
+            id: RequestId::Number(1),
+            method: "initialize".to_string(),
+            params: Some(serde_json::json!({
+                "protocolVersion": "2099-01-01",
+                "capabilities": {},
+                "clientInfo": { "name": "future", "version": "9.0" }
+            })),
+        };
+        let mut ctx = TestContext { counter: 0 };
+        let result = server.dispatch_request(&request, &mut ctx).unwrap();
+        assert_eq!(result["protocolVersion"], PROTOCOL_VERSION);
+        assert_eq!(server.session().protocol_version(), Some(PROTOCOL_VERSION));
+    }
+
+    #[test]
+    fn test_tool_env_exposes_client_info() {
+        let mut server: Server<TestContext> = Server::new(ServerConfig::default());
+        server.add_tool(ClientInfoTool).unwrap();
+
+        let messages = vec![
+            make_request(
+                1,
+                "initialize",
+                Some(serde_json::json!({
+                    "protocolVersion": "2025-03-26",
+                    "capabilities": {},
+                    "clientInfo": { "name": "editor", "version": "2.0" }
+                })),
+            ),
+            make_request(
+                2,
+                "tools/call",
+                Some(serde_json::json!({ "name": "client_info" })),
+            ),
+        ];
+
+        let transport = Arc::new(Mutex::new(MockTransport::new(messages)));
+        let mut ctx = TestContext { counter: 0 };
+        server.process_one(transport.clone(), &mut ctx).unwrap();
+        server.process_one(transport.clone(), &mut ctx).unwrap();
+
+        let t = transport.lock().unwrap();
+        let json = serde_json::to_string(&t.get_responses()[1]).unwrap();
+        assert!(json.contains("editor sampling=false version=2025-03-26"));
+    }
+
+    // Tool that bumps a custom counter
+    struct MeteredTool;
+
+    impl Tool<TestContext> for MeteredTool {
+        fn name(&self) -> &str {
+            "metered"
+        }
+        fn description(&self) -> &str {
+            "Count calls"
+        }
+        fn schema(&self) -> Value {
+            serde_json::json!({"type": "object"})
+        }
+        fn execute(
+            &self,
+            _args: Value,
+            _ctx: &mut TestContext,
+            env: &ToolEnv,
+        ) -> Result<CallToolResult> {
+            env.metrics()
+                .inc("metered_calls_total", &[("kind", "test")]);
+            Ok(CallToolResult::text("ok"))
+        }
+    }
+
+    #[test]
+    fn test_metrics_recorded() {
+        let registry = Metrics::new();
+        let config = ServerConfig {
+            metrics: registry.clone(),
+            ..Default::default()
+        };
+        let mut server: Server<TestContext> = Server::new(config);
+        server.add_tool(MeteredTool).unwrap();
+
+        let messages = vec![
+            make_request(1, "initialize", None),
+            make_request(
+                2,
+                "tools/call",
+                Some(serde_json::json!({ "name": "metered" })),
+            ),
+            make_request(
+                3,
+                "tools/call",
+                Some(serde_json::json!({ "name": "missing" })),
+            ),
+            make_request(4, "no/such/method", None),
+        ];
+        let transport = Arc::new(Mutex::new(MockTransport::new(messages)));
+        let mut ctx = TestContext { counter: 0 };
+        for _ in 0..4 {
+            server.process_one(transport.clone(), &mut ctx).unwrap();
+        }
+
+        let requests = |method| registry.value(metrics::REQUESTS_TOTAL, &[("method", method)]);
+        assert_eq!(requests("initialize"), Some(1.0));
+        assert_eq!(requests("tools/call"), Some(2.0));
+        assert_eq!(requests("other"), Some(1.0));
+        assert_eq!(requests("no/such/method"), None);
+
+        assert_eq!(
+            registry.value(metrics::ERRORS_TOTAL, &[("code", "-32601")]),
+            Some(1.0)
+        );
+        assert_eq!(
+            registry.value(metrics::ERRORS_TOTAL, &[("code", "-32000")]),
+            Some(1.0)
+        );
+        assert_eq!(
+            registry.value("metered_calls_total", &[("kind", "test")]),
+            Some(1.0)
+        );
+
+        let text = registry.render();
+        assert!(text.contains("mcp_tool_duration_seconds_count{tool=\"metered\"} 1\n"));
+        assert!(!text.contains("tool=\"missing\""));
+    }
+
+    #[test]
+    fn test_metrics_method_cardinality() {
+        let registry = Metrics::new();
+        let config = ServerConfig {
+            metrics: registry.clone(),
+            ..Default::default()
+        };
+        let mut server: Server<TestContext> = Server::new(config);
+
+        // Rejected before dispatch, since the session isn't initialized
+        let messages: Vec<_> = (0..50)
+            .map(|i| make_request(i, &format!("random/{}", i), None))
+            .chain([make_request(50, "ping", None)])
+            .collect();
+        let count = messages.len();
+        let transport = Arc::new(Mutex::new(MockTransport::new(messages)));
+        let mut ctx = TestContext { counter: 0 };
+        for _ in 0..count {
+            server.process_one(transport.clone(), &mut ctx).unwrap();
+        }
+
+        let requests = |method| registry.value(metrics::REQUESTS_TOTAL, &[("method", method)]);
+        assert_eq!(requests("other"), Some(50.0));
+        assert_eq!(requests("ping"), Some(1.0));
+        assert!(!registry.render().contains("random/"));
+    }
+
+    // Transport that replays scripted reads, including failures
+    struct ScriptedTransport {
+        reads: std::collections::VecDeque<Result<JsonRpcMessage>>,
+        writes: Arc<Mutex<Vec<JsonRpcMessage>>>,
+    }
+
+    impl Transport for ScriptedTransport {
+        fn read(&mut self) -> Result<JsonRpcMessage> {
+            self.reads
+                .pop_front()
+                .unwrap_or(Err(McpError::TransportClosed))
+        }
+
+        fn write(&mut self, message: &JsonRpcMessage) -> Result<()> {
+            self.writes.lock().unwrap().push(message.clone());
+            Ok(())
+        }
+
+        fn close(&mut self) -> Result<()> {
+            Ok(())
+        }
+    }
+
+    #[test]
+    fn test_start_survives_malformed_messages() {
+        let mut server: Server<TestContext> = Server::new(ServerConfig::default());
+        let writes = Arc::new(Mutex::new(Vec::new()));
+        let json_err = serde_json::from_str::<Value>("{oops").unwrap_err();
+
+        let transport = ScriptedTransport {
+            reads: vec![
+                Err(McpError::Json(json_err)),
+                Err(McpError::InvalidMessage("not a JSON-RPC message".into())),
+                Err(McpError::MessageTooLarge(10)),
+                Ok(make_request(1, "ping", None)),
+            ]
+            .into(),
+            writes: writes.clone(),
+        };
+
+        let result = server.start(transport, TestContext { counter: 0 });
+        assert!(result.is_ok());
+
+        let writes = writes.lock().unwrap();
+        assert_eq!(writes.len(), 4);
+        assert_eq!(error_code(&writes[0]), Some(-32700));
+        assert_eq!(error_code(&writes[1]), Some(-32600));
+        assert_eq!(error_code(&writes[2]), Some(-32600));
+        for rejected in &writes[..3] {
+            if let JsonRpcMessage::Response(resp) = rejected {
+                assert_eq!(resp.id, RequestId::Null);
+            }
+        }
+        // Session kept going and answered the ping
+        assert_eq!(error_code(&writes[3]), None);
+    }
+
+    #[test]
+    fn test_start_stops_on_io_error() {
+        let mut server: Server<TestContext> = Server::new(ServerConfig::default());
+        let io_err = std::io::Error::new(std::io::ErrorKind::BrokenPipe, "gone");
+        let transport = ScriptedTransport {
+            reads: vec![Err(McpError::Io(io_err))].into(),
+            writes: Arc::new(Mutex::new(Vec::new())),
+        };
+
+        let result = server.start(transport, TestContext { counter: 0 });
+        assert!(matches!(result, Err(McpError::Io(_))));
+    }
+
+    // Writer shared with the test so output survives `start` consuming the transport
+    #[derive(Clone, Default)]
+    struct SharedBuf(Arc<Mutex<Vec<u8>>>);
+
+    impl std::io::Write for SharedBuf {
+        fn write(&mut self, buf: &[u8]) -> std::io::Result<usize> {
+            self.0.lock().unwrap().extend_from_slice(buf);
+            Ok(buf.len())
+        }
+
+        fn flush(&mut self) -> std::io::Result<()> {
+            Ok(())
+        }
+    }
+
+    #[test]
+    fn test_start_over_stream_transport() {
+        let mut server: Server<TestContext> = Server::new(ServerConfig::default());
+        server.add_tool(IncrementTool).unwrap();
+
+        let input = [
+            r#"{"jsonrpc":"2.0","id":1,"method":"initialize"}"#,
+            r#"{"jsonrpc":"2.0","method":"notifications/initialized"}"#,
+            "this line is not json",
+            r#"{"jsonrpc":"2.0","id":2,"method":"tools/call","params":{"name":"increment","arguments":{"amount":3}}}"#,
+        ]
+        .join("\n");
+        let output = SharedBuf::default();
+        let transport =
+            crate::transport::StreamTransport::new(std::io::Cursor::new(input), output.clone());
+
+        server.start(transport, TestContext { counter: 0 }).unwrap();
+
+        let output = String::from_utf8(output.0.lock().unwrap().clone()).unwrap();
+        let lines: Vec<Value> = output
+            .lines()
+            .map(|l| serde_json::from_str(l).unwrap())
+            .collect();
+        assert_eq!(lines.len(), 3);
+        assert_eq!(lines[0]["id"], 1);
+        assert_eq!(lines[1]["error"]["code"], -32700);
+        assert!(lines[1]["id"].is_null());
+        assert_eq!(lines[2]["id"], 2);
+        assert_eq!(
+            lines[2]["result"]["content"][0]["text"],
+            "Counter is now: 3"
+        );
+    }
+
+    #[cfg(feature = "tracing")]
+    mod tracing_tests {
+        use super::*;
+        use tracing::field::{Field, Visit};
+        use tracing::span::{Attributes, Id, Record};
+        use tracing::{Event, Metadata, Subscriber};
+
+        /// A span as seen by the recorder
+        struct SpanRecord {
+            name: &'static str,
+            parent: Option<u64>,
+            fields: HashMap<String, String>,
+        }
+
+        struct Fields<'a>(&'a mut HashMap<String, String>);
+
+        impl Visit for Fields<'_> {
+            fn record_str(&mut self, field: &Field, value: &str) {
+                self.0.insert(field.name().to_string(), value.to_string());
+            }
+            fn record_debug(&mut self, field: &Field, value: &dyn std::fmt::Debug) {
+                self.0
+                    .insert(field.name().to_string(), format!("{:?}", value));
+            }
+        }
+
+        /// Minimal single-threaded subscriber that keeps every span
+        #[derive(Clone, Default)]
+        struct Recorder {
+            spans: Arc<Mutex<Vec<SpanRecord>>>,
+            stack: Arc<Mutex<Vec<u64>>>,
+        }
+
+        impl Recorder {
+            fn find(&self, name: &str) -> (u64, HashMap<String, String>, Option<u64>) {
+                let spans = self.spans.lock().unwrap();
+                let (i, span) = spans
+                    .iter()
+                    .enumerate()
+                    .find(|(_, s)| s.name == name)
+                    .unwrap_or_else(|| panic!("no span {}", name));
+                (i as u64 + 1, span.fields.clone(), span.parent)
+            }
+        }
+
+        impl Subscriber for Recorder {
+            fn enabled(&self, _: &Metadata<'_>) -> bool {
+                true
+            }
+            fn new_span(&self, attrs: &Attributes<'_>) -> Id {
+                let parent = if attrs.is_contextual() {
+                    self.stack.lock().unwrap().last().copied()
+                } else {
+                    attrs.parent().map(|id| id.into_u64())
+                };
+                let mut fields = HashMap::new();
+                attrs.record(&mut Fields(&mut fields));
+                let mut spans = self.spans.lock().unwrap();
+                spans.push(SpanRecord {
+                    name: attrs.metadata().name(),
+                    parent,
+                    fields,
+                });
+                Id::from_u64(spans.len() as u64)
+            }
+            fn record(&self, span: &Id, values: &Record<'_>) {
+                let mut spans = self.spans.lock().unwrap();
+                let span = &mut spans[span.into_u64() as usize - 1];
+                values.record(&mut Fields(&mut span.fields));
+            }
+            fn record_follows_from(&self, _: &Id, _: &Id) {}
+            fn event(&self, _: &Event<'_>) {}
+            fn enter(&self, span: &Id) {
+                self.stack.lock().unwrap().push(span.into_u64());
+            }
+            fn exit(&self, _: &Id) {
+                self.stack.lock().unwrap().pop();
+            }
+        }
+
+        struct ChildSpanTool;
+
+        impl Tool<TestContext> for ChildSpanTool {
+            fn name(&self) -> &str {
+                "traced"
+            }
+            fn description(&self) -> &str {
+                "Opens a child span"
+            }
+            fn schema(&self) -> Value {
+                serde_json::json!({ "type": "object" })
+            }
+            fn execute(
+                &self,
+                _args: Value,
+                _ctx: &mut TestContext,
+                env: &ToolEnv,
+            ) -> Result<CallToolResult> {
+                let _child = tracing::info_span!(parent: env.span(), "db.query").entered();
+                Ok(CallToolResult::text("done"))
+            }
+        }
+
+        #[test]
+        fn test_request_and_tool_spans() {
+            let mut server: Server<TestContext> = Server::new(ServerConfig {
+                strict_lifecycle: false,
+                ..Default::default()
+            });
+            server.add_tool(ChildSpanTool).unwrap();
+            server.transport = Some(Arc::new(Mutex::new(MockTransport::new(vec![]))));
+
+            let recorder = Recorder::default();
+            tracing::subscriber::with_default(recorder.clone(), || {
+                let message = make_request(
+                    1,
+                    "tools/call",
+                    Some(serde_json::json!({
+                        "name": "traced",
+                        "_meta": {
+                            "traceparent": "00-4bf92f3577b34da6a3ce929d0e0e4736-00f067aa0ba902b7-01"
+                        }
+                    })),
+                );
+                let mut ctx = TestContext { counter: 0 };
+                server.handle_message(message, &mut ctx).unwrap();
+            });
+
+            let (request_id, request, _) = recorder.find("mcp.request");
+            assert_eq!(request["method"], "tools/call");
+            assert_eq!(request["tool"], "traced");
+            assert_eq!(request["outcome"], "ok");
+            assert_eq!(request["trace_id"], "4bf92f3577b34da6a3ce929d0e0e4736");
+            assert_eq!(request["parent_span_id"], "00f067aa0ba902b7");
+            assert!(request.contains_key("duration_ms"));
+
+            let (tool_id, tool, parent) = recorder.find("mcp.tool");
+            assert_eq!(parent, Some(request_id));
+            assert_eq!(tool["outcome"], "ok");
+
+            let (_, _, parent) = recorder.find("db.query");
+            assert_eq!(parent, Some(tool_id));
+        }
+
+        #[test]
+        fn test_failed_tool_span_records_error() {
+            let mut server: Server<TestContext> = Server::new(ServerConfig {
+                strict_lifecycle: false,
+                ..Default::default()
+            });
+            server.add_tool(FailingTool).unwrap();
+            server.transport = Some(Arc::new(Mutex::new(MockTransport::new(vec![]))));
+
+            let recorder = Recorder::default();
+            tracing::subscriber::with_default(recorder.clone(), || {
+                let params = serde_json::json!({ "name": FailingTool.name() });
+                let message = make_request(1, "tools/call", Some(params));
+                let mut ctx = TestContext { counter: 0 };
+                server.handle_message(message, &mut ctx).unwrap();
+            });
+
+            let (_, request, _) = recorder.find("mcp.request");
+            assert_eq!(request["outcome"], "error");
+            assert_eq!(request["error_code"], "-32000");
+            let (_, tool, _) = recorder.find("mcp.tool");
+            assert_eq!(tool["outcome"], "error");
+        }
+    }
+
+    #[cfg(feature = "auth")]
+    struct AdminTool;
+
+    #[cfg(feature = "auth")]
+    impl Tool<TestContext> for AdminTool {
+        fn name(&self) -> &str {
+            "admin"
+        }
+        fn description(&self) -> &str {
+            "Needs the admin scope"
+        }
+        fn schema(&self) -> Value {
+            serde_json::json!({ "type": "object" })
+        }
+        fn required_scopes(&self) -> &[&str] {
+            &["admin", "files:write"]
+        }
+        fn execute(
+            &self,
+            _args: Value,
+            _ctx: &mut TestContext,
+            env: &ToolEnv,
+        ) -> Result<CallToolResult> {
+            let user = env.claims().map(|c| c.user_id()).unwrap_or_default();
+            Ok(CallToolResult::text(format!("hello {}", user)))
+        }
+    }
+
+    #[cfg(feature = "auth")]
+    struct PlansResource;
+
+    #[cfg(feature = "auth")]
+    impl Resource for PlansResource {
+        fn uri(&self) -> String {
+            "secret://plans".into()
+        }
+        fn name(&self) -> String {
+            "plans".into()
+        }
+        fn description(&self) -> String {
+            "Needs the admin scope".into()
+        }
+        fn mime_type(&self) -> String {
+            "text/plain".into()
+        }
+        fn content(&self) -> Vec<ResourceContent> {
+            vec![]
+        }
+        fn required_scopes(&self) -> &[&str] {
+            &["admin"]
+        }
+    }
+
+    #[cfg(feature = "auth")]
+    struct PlansPrompt;
+
+    #[cfg(feature = "auth")]
+    impl PromptDef for PlansPrompt {
+        fn name(&self) -> &str {
+            "plans"
+        }
+        fn description(&self) -> Option<&str> {
+            None
+        }
+        fn arguments(&self) -> Vec<PromptArgument> {
+            vec![]
+        }
+        fn get_messages(&self, _args: &HashMap<String, String>) -> Result<Vec<PromptMessage>> {
+            Ok(vec![])
+        }
+        fn required_scopes(&self) -> &[&str] {
+            &["admin"]
+        }
+    }
+
+    /// Reports which resources a tool can see through its `ToolEnv`
+    #[cfg(feature = "auth")]
+    struct ResourcesTool;
+
+    #[cfg(feature = "auth")]
+    impl Tool<TestContext> for ResourcesTool {
+        fn name(&self) -> &str {
+            "resources"
+        }
+        fn description(&self) -> &str {
+            "List visible resources"
+        }
+        fn schema(&self) -> Value {
+            serde_json::json!({ "type": "object" })
+        }
+        fn execute(
+            &self,
+            _args: Value,
+            _ctx: &mut TestContext,
+            env: &ToolEnv,
+        ) -> Result<CallToolResult> {
+            let mut uris = env.list_resources();
+            uris.sort();
+            let plans = env.get_resource("secret://plans").is_some();
+            Ok(CallToolResult::text(format!("{:?} {}", uris, plans)))
+        }
+    }
+
+    #[cfg(feature = "auth")]
+    #[test]
+    fn test_scoped_resources_and_prompts() {
+        let mut server: Server<TestContext> = Server::new(ServerConfig {
+            strict_lifecycle: false,
+            ..Default::default()
+        });
+        server.add_tool(ResourcesTool).unwrap();
+        server.add_resource(PlansResource).unwrap();
+        server
+            .add_resource(TestResource {
+                uri: "test://public".into(),
+                data: "hello".into(),
+            })
+            .unwrap();
+        server.add_prompt(PlansPrompt).unwrap();
+        let transport: Arc<Mutex<dyn Transport>> = Arc::new(Mutex::new(MockTransport::new(vec![])));
+        server.transport = Some(transport);
+        let mut ctx = TestContext { counter: 0 };
+        server.set_claims(Some(Claims {
+            sub: "user-1".into(),
+            scope: Some("read".into()),
+            ..Default::default()
+        }));
+
+        let mut error = |server: &mut Server<TestContext>, method: &str, params: Value| {
+            let request = JsonRpcRequest {
+                jsonrpc: Default::default(),
+                id: RequestId::Number(1),
+                method: method.to_string(),
+                params: Some(params),
+            };
+            let JsonRpcMessage::Response(response) = server.handle_request(&request, &mut ctx)
+            else {
+                panic!("expected a response");
+            };
+            let error = response.error.unwrap();
+            (error.code, error.message, error.data)
+        };
+
+        // Items the caller lacks scopes for are refused, not reported missing
+        let refused = error(
+            &mut server,
+            "resources/read",
+            serde_json::json!({ "uri": "secret://plans" }),
+        );
+        assert_eq!(refused.0, -32003);
+        assert_eq!(refused.2.unwrap()["scope"], "admin");
+        assert_eq!(server.take_denied_scope().as_deref(), Some("admin"));
+
+        let refused = error(
+            &mut server,
+            "prompts/get",
+            serde_json::json!({ "name": "plans" }),
+        );
+        assert_eq!(refused.0, -32003);
+        assert_eq!(server.take_denied_scope().as_deref(), Some("admin"));
+
+        let missing = error(
+            &mut server,
+            "prompts/get",
+            serde_json::json!({ "name": "other" }),
+        );
+        assert_ne!(missing.0, -32003);
+        assert_eq!(server.take_denied_scope(), None);
+
+        // Tools can't reach hidden resources either
+        let call = JsonRpcRequest {
+            jsonrpc: Default::default(),
+            id: RequestId::Number(2),
+            method: "tools/call".to_string(),
+            params: Some(serde_json::json!({ "name": "resources" })),
+        };
+        let result = server.dispatch_request(&call, &mut ctx).unwrap();
+        assert_eq!(result["content"][0]["text"], r#"["test://public"] false"#);
+
+        server.set_claims(Some(Claims {
+            sub: "user-1".into(),
+            scope: Some("admin".into()),
+            ..Default::default()
+        }));
+        let result = server.dispatch_request(&call, &mut ctx).unwrap();
+        assert_eq!(
+            result["content"][0]["text"],
+            r#"["secret://plans", "test://public"] true"#
+        );
+    }
+
+    #[cfg(feature = "auth")]
+    #[test]
+    fn test_required_scopes() {
+        let mut server: Server<TestContext> = Server::new(ServerConfig {
+            strict_lifecycle: false,
+            ..Default::default()
+        });
+        server.add_tool(IncrementTool).unwrap();
+        server.add_tool(AdminTool).unwrap();
+        let transport: Arc<Mutex<dyn Transport>> = Arc::new(Mutex::new(MockTransport::new(vec![])));
+        server.transport = Some(transport);
+        let mut ctx = TestContext { counter: 0 };
+
+        let list = JsonRpcRequest {
+            jsonrpc: Default::default(),
+            id: RequestId::Number(1),
+            method: "tools/list".to_string(),
+            params: None,
+        };
+        let call = JsonRpcRequest {
+            jsonrpc: Default::default(),
+            id: RequestId::Number(2),
+            method: "tools/call".to_string(),
+            params: Some(serde_json::json!({ "name": "admin" })),
+        };
+        let listed = |server: &mut Server<TestContext>, ctx: &mut TestContext| {
+            let result = server.dispatch_request(&list, ctx).unwrap();
+            result["tools"].as_array().unwrap().len()
+        };
+
+        // Without claims nothing is restricted
+        assert_eq!(listed(&mut server, &mut ctx), 2);
+
+        server.set_claims(Some(Claims {
+            sub: "user-1".into(),
+            scope: Some("files:write".into()),
+            ..Default::default()
+        }));
+        assert_eq!(listed(&mut server, &mut ctx), 1);
+        let JsonRpcMessage::Response(response) = server.handle_request(&call, &mut ctx) else {
+            panic!("expected a response");
+        };
+        let error = response.error.unwrap();
+        assert_eq!(error.code, -32003);
+        assert_eq!(
+            error.data,
+            Some(serde_json::json!({
+                "error": "insufficient_scope",
+                "scope": "admin files:write",
+            }))
+        );
+        assert_eq!(
+            server.take_denied_scope().as_deref(),
+            Some("admin files:write")
+        );
+        assert_eq!(server.take_denied_scope(), None);
+
+        server.set_claims(Some(Claims {
+            sub: "user-1".into(),
+            scope: Some("admin files:write".into()),
+            ..Default::default()
+        }));
+        assert_eq!(listed(&mut server, &mut ctx), 2);
+        let result = server.dispatch_request(&call, &mut ctx).unwrap();
+        assert_eq!(result["content"][0]["text"], "hello user-1");
+    }
 }
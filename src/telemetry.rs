@@ -0,0 +1,212 @@
+//! Tracing Integration
+//!
+//! With the `tracing` feature, requests, tool calls, resource reads and
+//! prompt gets run inside `tracing` spans:
+//!
+//! - `mcp.http` - one HTTP request: `session_id` (shortened), `tenant`, `trace_id`,
+//!   `parent_span_id`
+//! - `mcp.request` - one JSON-RPC request: `method`, `tool`, `outcome`, `error_code`,
+//!   `duration_ms`, plus `trace_id`/`parent_span_id` from `_meta.traceparent`
+//! - `mcp.tool`, `mcp.resource`, `mcp.prompt` - the handler itself
+//!
+//! W3C `traceparent` values (from the HTTP header or a request's `_meta`)
+//! are recorded as fields so an exporter can attach the spans to the
+//! caller's trace.
+
+use crate::types::{JsonRpcRequest, Result};
+use std::time::Instant;
+use tracing::Span;
+use tracing::field::Empty;
+
+/// A parsed W3C `traceparent` header
+///
+/// Format: `{version}-{trace-id}-{parent-id}-{flags}`, e.g.
+/// `00-4bf92f3577b34da6a3ce929d0e0e4736-00f067aa0ba902b7-01`.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct TraceParent {
+    /// 32 lowercase hex digits
+    pub trace_id: String,
+    /// 16 lowercase hex digits identifying the caller's span
+    pub parent_id: String,
+    /// Trace flags (bit 0: sampled)
+    pub flags: u8,
+}
+
+impl TraceParent {
+    /// Parse a `traceparent` value, returning `None` if it is malformed
+    pub fn parse(value: &str) -> Option<Self> {
+        let mut parts = value.trim().split('-');
+        let version = parts.next()?;
+        let trace_id = parts.next()?;
+        let parent_id = parts.next()?;
+        let flags = parts.next()?;
+
+        // Version 00 has exactly four fields; later versions may append more
+        if !is_hex(version, 2) || version == "ff" || (version == "00" && parts.next().is_some()) {
+            return None;
+        }
+        if !is_hex(trace_id, 32) || trace_id.bytes().all(|b| b == b'0') {
+            return None;
+        }
+        if !is_hex(parent_id, 16) || parent_id.bytes().all(|b| b == b'0') {
+            return None;
+        }
+        if !is_hex(flags, 2) {
+            return None;
+        }
+
+        Some(Self {
+            trace_id: trace_id.to_string(),
+            parent_id: parent_id.to_string(),
+            flags: u8::from_str_radix(flags, 16).ok()?,
+        })
+    }
+
+    /// True if the caller sampled this trace
+    pub fn sampled(&self) -> bool {
+        self.flags & 1 == 1
+    }
+
+    /// Record the trace fields on `span`
+    pub(crate) fn record(&self, span: &Span) {
+        span.record("trace_id", self.trace_id.as_str());
+        span.record("parent_span_id", self.parent_id.as_str());
+    }
+}
+
+impl std::fmt::Display for TraceParent {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(
+            f,
+            "00-{}-{}-{:02x}",
+            self.trace_id, self.parent_id, self.flags
+        )
+    }
+}
+
+/// Lowercase hex of exactly `len` digits
+fn is_hex(s: &str, len: usize) -> bool {
+    s.len() == len && s.bytes().all(|b| matches!(b, b'0'..=b'9' | b'a'..=b'f'))
+}
+
+/// Span around one HTTP request
+///
+/// `session_id` should already be shortened, as it is for logs.
+#[cfg(feature = "http")]
+pub(crate) fn http_span(method: &str, session_id: Option<&str>, traceparent: Option<&str>) -> Span {
+    let span = tracing::info_span!(
+        "mcp.http",
+        http.method = method,
+        session_id = Empty,
+        tenant = Empty,
+        trace_id = Empty,
+        parent_span_id = Empty,
+    );
+    if let Some(id) = session_id {
+        span.record("session_id", id);
+    }
+    if let Some(parent) = traceparent.and_then(TraceParent::parse) {
+        parent.record(&span);
+    }
+    span
+}
+
+/// Span around one JSON-RPC request, closed by `finish`
+pub(crate) struct RequestSpan {
+    span: Span,
+    started: Instant,
+}
+
+impl RequestSpan {
+    pub(crate) fn new(request: &JsonRpcRequest) -> Self {
+        let span = tracing::info_span!(
+            "mcp.request",
+            method = request.method.as_str(),
+            id = %request.id,
+            tool = Empty,
+            outcome = Empty,
+            error_code = Empty,
+            duration_ms = Empty,
+            trace_id = Empty,
+            parent_span_id = Empty,
+        );
+
+        let params = request.params.as_ref();
+        if request.method == "tools/call" {
+            if let Some(tool) = params.and_then(|p| p.get("name")).and_then(|n| n.as_str()) {
+                span.record("tool", tool);
+            }
+        }
+        if let Some(parent) = params
+            .and_then(|p| p.pointer("/_meta/traceparent"))
+            .and_then(|v| v.as_str())
+            .and_then(TraceParent::parse)
+        {
+            parent.record(&span);
+        }
+
+        Self {
+            span,
+            started: Instant::now(),
+        }
+    }
+
+    pub(crate) fn span(&self) -> &Span {
+        &self.span
+    }
+
+    /// Record outcome and duration
+    pub(crate) fn finish<T>(self, result: &Result<T>) {
+        record_outcome(&self.span, result);
+        self.span
+            .record("duration_ms", self.started.elapsed().as_millis() as u64);
+    }
+}
+
+/// Record `ok`/`error` (and the JSON-RPC error code) on `span`
+pub(crate) fn record_outcome<T>(span: &Span, result: &Result<T>) {
+    match result {
+        Ok(_) => {
+            span.record("outcome", "ok");
+        }
+        Err(e) => {
+            span.record("outcome", "error");
+            span.record("error_code", e.to_jsonrpc_error().code as i64);
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const EXAMPLE: &str = "00-4bf92f3577b34da6a3ce929d0e0e4736-00f067aa0ba902b7-01";
+
+    #[test]
+    fn test_traceparent_parse() {
+        let parent = TraceParent::parse(EXAMPLE).unwrap();
+        assert_eq!(parent.trace_id, "4bf92f3577b34da6a3ce929d0e0e4736");
+        assert_eq!(parent.parent_id, "00f067aa0ba902b7");
+        assert!(parent.sampled());
+        assert_eq!(parent.to_string(), EXAMPLE);
+
+        // Future versions may carry extra fields
+        assert!(TraceParent::parse(&format!("01{}-extra", &EXAMPLE[2..])).is_some());
+    }
+
+    #[test]
+    fn test_traceparent_rejects_malformed() {
+        for value in [
+            "",
+            "00-4bf92f3577b34da6a3ce929d0e0e4736-00f067aa0ba902b7",
+            "00-4bf92f3577b34da6a3ce929d0e0e4736-00f067aa0ba902b7-01-extra",
+            "ff-4bf92f3577b34da6a3ce929d0e0e4736-00f067aa0ba902b7-01",
+            "00-00000000000000000000000000000000-00f067aa0ba902b7-01",
+            "00-4bf92f3577b34da6a3ce929d0e0e4736-0000000000000000-01",
+            "00-4BF92F3577B34DA6A3CE929D0E0E4736-00f067aa0ba902b7-01",
+            "00-4bf92f3577b34da6a3ce929d0e0e473-00f067aa0ba902b7-01",
+        ] {
+            assert!(TraceParent::parse(value).is_none(), "{}", value);
+        }
+    }
+}
@@ -0,0 +1,176 @@
+//! Connection Threads
+//!
+//! Bookkeeping for the socket servers that run each connection on a thread
+//! of its own (`UnixServer`, `WebSocketServer`): a cap on how many run at
+//! once, and a handle that stops the accept loop from another thread.
+
+use std::net::Shutdown;
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::{Arc, Mutex};
+use std::thread::JoinHandle;
+
+/// Most connections served at once unless configured otherwise
+pub(crate) const DEFAULT_MAX_CONNECTIONS: usize = 256;
+
+type Wake = Box<dyn Fn() + Send + Sync>;
+
+/// Handle for stopping a running server from another thread
+#[derive(Clone, Default)]
+pub struct ShutdownHandle {
+    stopped: Arc<AtomicBool>,
+    /// Unblocks the accept loop while the server is listening
+    wake: Arc<Mutex<Option<Wake>>>,
+}
+
+impl ShutdownHandle {
+    /// Stop accepting connections, close open sessions, and return from `serve`
+    ///
+    /// Shutting down before `serve` is called makes it return at once.
+    pub fn shutdown(&self) {
+        self.stopped.store(true, Ordering::SeqCst);
+
+        let wake = self.wake.lock().unwrap_or_else(|e| e.into_inner());
+        if let Some(wake) = wake.as_ref() {
+            wake();
+        }
+    }
+
+    pub(crate) fn is_stopped(&self) -> bool {
+        self.stopped.load(Ordering::SeqCst)
+    }
+
+    /// Set how to unblock the accept loop, returning false if already stopped
+    ///
+    /// A shutdown that ran before `wake` was set had nothing to unblock, so
+    /// the caller must not start accepting in that case.
+    pub(crate) fn listening(&self, wake: impl Fn() + Send + Sync + 'static) -> bool {
+        *self.wake.lock().unwrap_or_else(|e| e.into_inner()) = Some(Box::new(wake));
+        !self.is_stopped()
+    }
+
+    /// Forget the wake-up once the listener is gone
+    pub(crate) fn closed(&self) {
+        *self.wake.lock().unwrap_or_else(|e| e.into_inner()) = None;
+    }
+}
+
+/// A stream whose connection can be closed from another thread
+pub(crate) trait Close {
+    fn close(&self);
+}
+
+#[cfg(unix)]
+impl Close for std::os::unix::net::UnixStream {
+    fn close(&self) {
+        let _ = self.shutdown(Shutdown::Both);
+    }
+}
+
+#[cfg(feature = "websocket")]
+impl Close for std::net::TcpStream {
+    fn close(&self) {
+        let _ = self.shutdown(Shutdown::Both);
+    }
+}
+
+/// Running connection threads, up to a limit
+pub(crate) struct Connections<S: Close> {
+    max: usize,
+    open: Vec<(S, JoinHandle<()>)>,
+}
+
+impl<S: Close> Connections<S> {
+    pub(crate) fn new(max: usize) -> Self {
+        Self {
+            max: max.max(1),
+            open: Vec::new(),
+        }
+    }
+
+    /// True if another connection may start, forgetting those that ended
+    pub(crate) fn has_room(&mut self) -> bool {
+        self.open.retain(|(_, handle)| !handle.is_finished());
+        self.open.len() < self.max
+    }
+
+    /// Track a connection's thread, with a clone of its stream to close it by
+    pub(crate) fn push(&mut self, control: S, handle: JoinHandle<()>) {
+        self.open.push((control, handle));
+    }
+
+    /// Close every connection and wait for its thread to end
+    ///
+    /// Closing the read side ends each session loop with `TransportClosed`.
+    pub(crate) fn close_all(self) {
+        for (control, handle) in self.open {
+            control.close();
+            let _ = handle.join();
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::sync::atomic::AtomicUsize;
+    use std::thread;
+
+    struct Counted(Arc<AtomicUsize>);
+
+    impl Close for Counted {
+        fn close(&self) {
+            self.0.fetch_add(1, Ordering::SeqCst);
+        }
+    }
+
+    #[test]
+    fn test_connections_capped() {
+        let closed = Arc::new(AtomicUsize::new(0));
+        let mut connections = Connections::new(2);
+        let (release, wait) = std::sync::mpsc::channel::<()>();
+        let wait = Arc::new(Mutex::new(wait));
+
+        for _ in 0..2 {
+            assert!(connections.has_room());
+            let wait = wait.clone();
+            let handle = thread::spawn(move || {
+                let _ = wait.lock().unwrap().recv();
+            });
+            connections.push(Counted(closed.clone()), handle);
+        }
+        assert!(!connections.has_room());
+
+        // A finished connection frees its slot
+        release.send(()).unwrap();
+        while !connections.has_room() {
+            thread::yield_now();
+        }
+
+        drop(release);
+        connections.close_all();
+        assert_eq!(closed.load(Ordering::SeqCst), 1);
+    }
+
+    #[test]
+    fn test_shutdown_before_listening() {
+        let woken = Arc::new(AtomicUsize::new(0));
+        let wake = {
+            let woken = woken.clone();
+            move || {
+                woken.fetch_add(1, Ordering::SeqCst);
+            }
+        };
+
+        let handle = ShutdownHandle::default();
+        handle.shutdown();
+        assert!(!handle.listening(wake.clone()));
+
+        let handle = ShutdownHandle::default();
+        assert!(handle.listening(wake));
+        handle.shutdown();
+        assert_eq!(woken.load(Ordering::SeqCst), 1);
+        handle.closed();
+        handle.shutdown();
+        assert_eq!(woken.load(Ordering::SeqCst), 1);
+    }
+}
@@ -0,0 +1,114 @@
+//! Health Probes
+//!
+//! Liveness and readiness routes for load balancers. `HttpServer` answers
+//! them on its accept thread, ahead of the queue of MCP requests, so a probe
+//! gets through while a slow tool call is still running.
+
+use crate::types::{McpError, Result};
+use serde_json::{Value, json};
+use std::panic::{AssertUnwindSafe, catch_unwind};
+use std::sync::Arc;
+
+/// Readiness check: `Err` marks the server as not ready
+pub(crate) type ReadyCheck = dyn Fn() -> Result<()> + Send + Sync;
+
+/// Configured probe paths
+#[derive(Clone, Default)]
+pub(crate) struct Probes {
+    health: Option<String>,
+    ready: Option<(String, Arc<ReadyCheck>)>,
+}
+
+impl Probes {
+    pub(crate) fn set_health(&mut self, path: String) {
+        self.health = Some(path);
+    }
+
+    pub(crate) fn set_ready(&mut self, path: String, check: Arc<ReadyCheck>) {
+        self.ready = Some((path, check));
+    }
+
+    pub(crate) fn is_empty(&self) -> bool {
+        self.health.is_none() && self.ready.is_none()
+    }
+
+    /// True if `path` is a probe route
+    pub(crate) fn matches(&self, path: &str) -> bool {
+        self.health.as_deref() == Some(path)
+            || self.ready.as_ref().is_some_and(|(ready, _)| ready == path)
+    }
+
+    /// Status and JSON body for the probe at `path`
+    ///
+    /// Liveness always succeeds; readiness runs the check, and a check that
+    /// fails or panics gives `503`.
+    pub(crate) fn reply(&self, path: &str, name: &str, version: &str) -> (u16, Value) {
+        let check = match &self.ready {
+            Some((ready, check)) if ready == path => check,
+            _ => return (200, body("ok", name, version)),
+        };
+
+        let outcome = catch_unwind(AssertUnwindSafe(|| check()))
+            .unwrap_or_else(|_| Err(McpError::Internal("Readiness check panicked".into())));
+        match outcome {
+            Ok(()) => (200, body("ready", name, version)),
+            Err(e) => {
+                log::warn!("Readiness check failed: {}", e);
+                let mut body = body("unavailable", name, version);
+                body["error"] = e.to_string().into();
+                (503, body)
+            }
+        }
+    }
+}
+
+fn body(status: &str, name: &str, version: &str) -> Value {
+    json!({ "status": status, "name": name, "version": version })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_liveness() {
+        let mut probes = Probes::default();
+        assert!(probes.is_empty());
+        probes.set_health("/healthz".into());
+        assert!(probes.matches("/healthz"));
+        assert!(!probes.matches("/readyz"));
+
+        let (status, body) = probes.reply("/healthz", "srv", "1.0");
+        assert_eq!(status, 200);
+        assert_eq!(
+            body,
+            json!({ "status": "ok", "name": "srv", "version": "1.0" })
+        );
+    }
+
+    #[test]
+    fn test_readiness_check() {
+        let mut probes = Probes::default();
+        probes.set_ready(
+            "/readyz".into(),
+            Arc::new(|| Err(McpError::Internal("database unreachable".into()))),
+        );
+        let (status, body) = probes.reply("/readyz", "srv", "1.0");
+        assert_eq!(status, 503);
+        assert_eq!(body["status"], "unavailable");
+        assert!(
+            body["error"]
+                .as_str()
+                .unwrap()
+                .contains("database unreachable")
+        );
+
+        probes.set_ready("/readyz".into(), Arc::new(|| panic!("boom")));
+        assert_eq!(probes.reply("/readyz", "srv", "1.0").0, 503);
+
+        probes.set_ready("/readyz".into(), Arc::new(|| Ok(())));
+        let (status, body) = probes.reply("/readyz", "srv", "1.0");
+        assert_eq!(status, 200);
+        assert_eq!(body["status"], "ready");
+    }
+}
@@ -0,0 +1,365 @@
+//! HTTP Error Responses
+//!
+//! Transport-level failures on the HTTP endpoint. Each one is answered with
+//! a JSON-RPC error body and a matching HTTP status, so clients can handle
+//! them the same way as errors from the server itself.
+
+use crate::types::{JsonRpcError, JsonRpcMessage, McpError, RequestId};
+
+/// JSON-RPC code for authentication and authorization failures
+const AUTH_ERROR_CODE: i32 = -32003;
+
+/// A request rejected before (or while) reaching the MCP server
+#[derive(Debug, Clone, PartialEq)]
+pub(crate) enum HttpError {
+    /// Body is not valid JSON
+    Parse(String),
+    /// Well-formed but unusable request
+    BadRequest(String),
+    /// No credentials were sent, and whether to challenge for a bearer token
+    #[cfg_attr(not(feature = "auth"), allow(dead_code))]
+    MissingCredentials { bearer: bool },
+    /// A bearer token was sent, but not in a usable form
+    #[cfg_attr(not(feature = "auth"), allow(dead_code))]
+    MalformedCredentials(String),
+    /// Credentials were sent but rejected
+    #[cfg_attr(not(feature = "auth"), allow(dead_code))]
+    InvalidToken(String),
+    /// Authenticated but not allowed
+    Forbidden(String),
+    /// Authenticated, but the token lacks these space-separated scopes
+    #[cfg_attr(not(feature = "auth"), allow(dead_code))]
+    InsufficientScope(String),
+    /// Unknown endpoint or session
+    NotFound(String),
+    /// HTTP method not supported on the endpoint
+    MethodNotAllowed,
+    /// Neither JSON nor SSE is acceptable to the client
+    NotAcceptable,
+    /// Body was not received in time
+    RequestTimeout,
+    /// Body exceeds the size limit (in bytes)
+    PayloadTooLarge(usize),
+    /// Body is not `application/json`
+    UnsupportedMediaType(String),
+    /// Request line and headers exceed the size limit (in bytes)
+    HeadersTooLarge(usize),
+    /// Anything else
+    Internal(String),
+}
+
+/// Status, extra headers and JSON body for an error response
+#[derive(Debug)]
+pub(crate) struct ErrorReply {
+    pub status: u16,
+    pub headers: Vec<(&'static str, String)>,
+    pub body: String,
+}
+
+impl HttpError {
+    /// Map the error to its HTTP response
+    ///
+    /// `id` is the request id if it could be read from the body, so the
+    /// client can match the error to its request.
+    pub(crate) fn reply(&self, id: RequestId) -> ErrorReply {
+        let (status, error) = match self {
+            HttpError::Parse(msg) => (400, JsonRpcError::parse_error(msg.clone())),
+            HttpError::BadRequest(msg) => (400, JsonRpcError::invalid_request(msg.clone())),
+            HttpError::MissingCredentials { .. } => (
+                401,
+                JsonRpcError::new(AUTH_ERROR_CODE, "Missing credentials"),
+            ),
+            HttpError::MalformedCredentials(msg) => (
+                400,
+                JsonRpcError::new(AUTH_ERROR_CODE, format!("Malformed credentials: {}", msg)),
+            ),
+            HttpError::InvalidToken(msg) => (
+                401,
+                JsonRpcError::new(AUTH_ERROR_CODE, format!("Invalid token: {}", msg)),
+            ),
+            HttpError::Forbidden(msg) => (
+                403,
+                JsonRpcError::new(AUTH_ERROR_CODE, format!("Forbidden: {}", msg)),
+            ),
+            HttpError::InsufficientScope(scope) => (
+                403,
+                JsonRpcError::new(
+                    AUTH_ERROR_CODE,
+                    format!("Insufficient scope: requires {}", scope),
+                )
+                .with_data(serde_json::json!({
+                    "error": "insufficient_scope",
+                    "scope": scope,
+                })),
+            ),
+            HttpError::NotFound(msg) => (404, JsonRpcError::invalid_request(msg.clone())),
+            HttpError::MethodNotAllowed => {
+                (405, JsonRpcError::invalid_request("Method not allowed"))
+            }
+            HttpError::NotAcceptable => (
+                406,
+                JsonRpcError::invalid_request(
+                    "Client must accept application/json or text/event-stream",
+                ),
+            ),
+            HttpError::RequestTimeout => (
+                408,
+                JsonRpcError::invalid_request("Timed out reading request body"),
+            ),
+            HttpError::PayloadTooLarge(limit) => (
+                413,
+                JsonRpcError::invalid_request(format!("Message exceeds {} bytes", limit)),
+            ),
+            HttpError::UnsupportedMediaType(content_type) => (
+                415,
+                JsonRpcError::invalid_request(format!(
+                    "Unsupported Content-Type: {}, expected application/json",
+                    content_type
+                )),
+            ),
+            HttpError::HeadersTooLarge(limit) => (
+                431,
+                JsonRpcError::invalid_request(format!("Headers exceed {} bytes", limit)),
+            ),
+            HttpError::Internal(msg) => (500, JsonRpcError::internal_error(msg.clone())),
+        };
+
+        let mut headers = vec![("Content-Type", "application/json".to_string())];
+        match self {
+            HttpError::MissingCredentials { bearer: true } => {
+                headers.push(("WWW-Authenticate", "Bearer".to_string()));
+            }
+            HttpError::MalformedCredentials(msg) => headers.push((
+                "WWW-Authenticate",
+                format!(
+                    "Bearer error=\"invalid_request\", error_description=\"{}\"",
+                    quotable(msg)
+                ),
+            )),
+            HttpError::InvalidToken(msg) => headers.push((
+                "WWW-Authenticate",
+                format!(
+                    "Bearer error=\"invalid_token\", error_description=\"{}\"",
+                    quotable(msg)
+                ),
+            )),
+            HttpError::InsufficientScope(scope) => headers.push((
+                "WWW-Authenticate",
+                format!(
+                    "Bearer error=\"insufficient_scope\", scope=\"{}\"",
+                    quotable(scope)
+                ),
+            )),
+            HttpError::MethodNotAllowed => headers.push(("Allow", "POST, DELETE".to_string())),
+            _ => {}
+        }
+
+        let body = serde_json::to_string(&JsonRpcMessage::error(id, error))
+            .expect("JSON-RPC error serializes");
+
+        ErrorReply {
+            status,
+            headers,
+            body,
+        }
+    }
+}
+
+impl ErrorReply {
+    /// Point a Bearer challenge at the protected resource metadata (RFC 9728)
+    pub(crate) fn with_resource_metadata(mut self, url: &str) -> Self {
+        for (name, value) in &mut self.headers {
+            if *name == "WWW-Authenticate" {
+                let param = format!("resource_metadata=\"{}\"", quotable(url));
+                *value = match value.as_str() {
+                    "Bearer" => format!("Bearer {}", param),
+                    _ => format!("{}, {}", value, param),
+                };
+            }
+        }
+        self
+    }
+}
+
+/// Make `value` safe inside a challenge's quoted-string
+///
+/// RFC 6750 limits attribute values to printable ASCII without `"` or `\`.
+fn quotable(value: &str) -> String {
+    value
+        .chars()
+        .filter(|c| c.is_ascii() && !c.is_ascii_control())
+        .map(|c| if c == '"' || c == '\\' { '\'' } else { c })
+        .collect()
+}
+
+impl From<McpError> for HttpError {
+    fn from(error: McpError) -> Self {
+        match error {
+            McpError::Json(e) => HttpError::Parse(e.to_string()),
+            McpError::InvalidMessage(msg) => HttpError::BadRequest(msg),
+            McpError::MessageTooLarge(limit) => HttpError::PayloadTooLarge(limit),
+            #[cfg(feature = "auth")]
+            McpError::Auth(msg) => HttpError::InvalidToken(msg),
+            #[cfg(feature = "auth")]
+            McpError::InsufficientScope(scope) => HttpError::InsufficientScope(scope),
+            other => HttpError::Internal(other.to_string()),
+        }
+    }
+}
+
+/// Best-effort read of the request id from a body that failed to process
+pub(crate) fn request_id(body: &str) -> RequestId {
+    serde_json::from_str::<serde_json::Value>(body)
+        .ok()
+        .and_then(|value| serde_json::from_value(value.get("id")?.clone()).ok())
+        .unwrap_or(RequestId::Null)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use serde_json::Value;
+
+    fn header<'a>(reply: &'a ErrorReply, name: &str) -> Option<&'a str> {
+        reply
+            .headers
+            .iter()
+            .find(|(n, _)| *n == name)
+            .map(|(_, v)| v.as_str())
+    }
+
+    fn body(reply: &ErrorReply) -> Value {
+        serde_json::from_str(&reply.body).unwrap()
+    }
+
+    #[test]
+    fn test_parse_error_reply() {
+        let error: HttpError = serde_json::from_str::<Value>("{oops")
+            .map_err(McpError::from)
+            .unwrap_err()
+            .into();
+        let reply = error.reply(RequestId::Null);
+
+        assert_eq!(reply.status, 400);
+        assert_eq!(header(&reply, "Content-Type"), Some("application/json"));
+        let body = body(&reply);
+        assert_eq!(body["jsonrpc"], "2.0");
+        assert_eq!(body["id"], Value::Null);
+        assert_eq!(body["error"]["code"], -32700);
+    }
+
+    #[test]
+    fn test_status_mapping() {
+        let cases = [
+            (HttpError::BadRequest("x".into()), 400, -32600),
+            (HttpError::Forbidden("x".into()), 403, -32003),
+            (HttpError::NotFound("x".into()), 404, -32600),
+            (HttpError::MethodNotAllowed, 405, -32600),
+            (HttpError::NotAcceptable, 406, -32600),
+            (HttpError::RequestTimeout, 408, -32600),
+            (HttpError::PayloadTooLarge(10), 413, -32600),
+            (
+                HttpError::UnsupportedMediaType("text/plain".into()),
+                415,
+                -32600,
+            ),
+            (HttpError::HeadersTooLarge(10), 431, -32600),
+            (HttpError::Internal("x".into()), 500, -32603),
+        ];
+        for (error, status, code) in cases {
+            let reply = error.reply(RequestId::Number(7));
+            assert_eq!(reply.status, status, "{:?}", error);
+            assert_eq!(body(&reply)["error"]["code"], code, "{:?}", error);
+            assert_eq!(body(&reply)["id"], 7);
+            assert!(header(&reply, "WWW-Authenticate").is_none());
+        }
+
+        let error: HttpError = McpError::MessageTooLarge(1024).into();
+        assert_eq!(error, HttpError::PayloadTooLarge(1024));
+        let error: HttpError = McpError::InvalidMessage("no method".into()).into();
+        assert_eq!(error.reply(RequestId::Null).status, 400);
+    }
+
+    #[test]
+    fn test_unauthorized_challenges() {
+        let reply = HttpError::MissingCredentials { bearer: true }.reply(RequestId::Null);
+        assert_eq!(reply.status, 401);
+        assert_eq!(header(&reply, "WWW-Authenticate"), Some("Bearer"));
+        assert_eq!(body(&reply)["error"]["message"], "Missing credentials");
+
+        // No bearer challenge when tokens aren't accepted
+        let reply = HttpError::MissingCredentials { bearer: false }.reply(RequestId::Null);
+        assert_eq!(reply.status, 401);
+        assert_eq!(header(&reply, "WWW-Authenticate"), None);
+
+        let reply = HttpError::InvalidToken("bad \"sig\"".into()).reply(RequestId::Null);
+        assert_eq!(reply.status, 401);
+        assert_eq!(
+            header(&reply, "WWW-Authenticate"),
+            Some("Bearer error=\"invalid_token\", error_description=\"bad 'sig'\"")
+        );
+        assert_eq!(body(&reply)["error"]["code"], -32003);
+
+        let reply = HttpError::InsufficientScope("files:write admin".into()).reply(RequestId::Null);
+        assert_eq!(reply.status, 403);
+        assert_eq!(
+            header(&reply, "WWW-Authenticate"),
+            Some("Bearer error=\"insufficient_scope\", scope=\"files:write admin\"")
+        );
+        assert_eq!(body(&reply)["error"]["data"]["error"], "insufficient_scope");
+
+        let reply = HttpError::MalformedCredentials("empty token\n".into()).reply(RequestId::Null);
+        assert_eq!(reply.status, 400);
+        assert_eq!(
+            header(&reply, "WWW-Authenticate"),
+            Some("Bearer error=\"invalid_request\", error_description=\"empty token\"")
+        );
+    }
+
+    #[test]
+    fn test_resource_metadata_challenge() {
+        let url = "https://mcp.example.com/.well-known/oauth-protected-resource/mcp";
+
+        let reply = HttpError::MissingCredentials { bearer: true }
+            .reply(RequestId::Null)
+            .with_resource_metadata(url);
+        assert_eq!(
+            header(&reply, "WWW-Authenticate"),
+            Some(format!("Bearer resource_metadata=\"{}\"", url).as_str())
+        );
+
+        let reply = HttpError::InvalidToken("expired".into())
+            .reply(RequestId::Null)
+            .with_resource_metadata(url);
+        assert_eq!(
+            header(&reply, "WWW-Authenticate"),
+            Some(
+                format!(
+                    "Bearer error=\"invalid_token\", error_description=\"expired\", \
+                     resource_metadata=\"{}\"",
+                    url
+                )
+                .as_str()
+            )
+        );
+
+        let reply = HttpError::NotFound("x".into())
+            .reply(RequestId::Null)
+            .with_resource_metadata(url);
+        assert!(header(&reply, "WWW-Authenticate").is_none());
+    }
+
+    #[test]
+    fn test_request_id() {
+        assert_eq!(
+            request_id(r#"{"jsonrpc":"2.0","id":5,"methd":"x"}"#),
+            RequestId::Number(5)
+        );
+        assert_eq!(
+            request_id(r#"{"id":"abc"}"#),
+            RequestId::String("abc".into())
+        );
+        assert_eq!(request_id("{oops"), RequestId::Null);
+        assert_eq!(request_id(r#"{"id":[1]}"#), RequestId::Null);
+    }
+}
@@ -3,16 +3,78 @@
 //! Abstracts communication between client and server.
 
 mod stdio;
+mod stream;
 
 #[cfg(feature = "http")]
 mod http;
 
+#[cfg(feature = "http")]
+mod health;
+
+#[cfg(feature = "http")]
+mod http_error;
+
+#[cfg(any(feature = "http", feature = "websocket"))]
+mod origin;
+
+#[cfg(feature = "http")]
+mod resource;
+
+#[cfg(feature = "http")]
+mod routes;
+
+#[cfg(feature = "http")]
+mod sessions;
+
+#[cfg(feature = "http")]
+mod workers;
+
+#[cfg(any(unix, feature = "websocket"))]
+mod connections;
+
+#[cfg(feature = "tls")]
+mod tls;
+
+#[cfg(unix)]
+mod unix;
+
+#[cfg(feature = "websocket")]
+mod websocket;
+
 pub use stdio::StdioTransport;
+pub use stream::{DEFAULT_MAX_MESSAGE_SIZE, Framing, StreamTransport};
+
+#[cfg(feature = "http")]
+pub use http::{HttpServer, HttpTransport, MountedApp};
+
+#[cfg(feature = "http")]
+pub use origin::Cors;
+
+#[cfg(feature = "http")]
+pub use resource::ProtectedResource;
 
 #[cfg(feature = "http")]
-pub use http::{HttpServer, HttpTransport};
+pub use routes::{RouteRequest, RouteResponse};
 
-use crate::types::{JsonRpcMessage, Result};
+#[cfg(feature = "tls")]
+pub use tls::{TlsConfig, TlsReloader};
+
+#[cfg(any(unix, feature = "websocket"))]
+pub use connections::ShutdownHandle;
+
+#[cfg(unix)]
+pub use unix::UnixServer;
+
+#[cfg(feature = "websocket")]
+pub use websocket::{WebSocketServer, WebSocketTransport};
+
+#[cfg(any(unix, feature = "http", feature = "websocket"))]
+use crate::server::Server;
+use crate::types::{JsonRpcMessage, McpError, Result};
+
+/// Setup function type for configuring tools on each server instance
+#[cfg(any(unix, feature = "http", feature = "websocket"))]
+pub(crate) type SetupFn<C> = Box<dyn Fn(&mut Server<C>) -> Result<()> + Send + Sync>;
 
 /// Transport trait - sync read/write of JSON-RPC messages
 pub trait Transport: Send + Sync {
@@ -25,3 +87,12 @@ pub trait Transport: Send + Sync {
     /// Close the transport
     fn close(&mut self) -> Result<()>;
 }
+
+/// Parse a JSON-RPC message from text
+///
+/// Malformed JSON is a `McpError::Json` (parse error), while well-formed JSON
+/// that isn't a JSON-RPC message is a `McpError::InvalidMessage`.
+pub(crate) fn parse_message(text: &str) -> Result<JsonRpcMessage> {
+    let value: serde_json::Value = serde_json::from_str(text)?;
+    serde_json::from_value(value).map_err(|e| McpError::InvalidMessage(e.to_string()))
+}
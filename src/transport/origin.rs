@@ -0,0 +1,350 @@
+//! Origin and Host Validation
+//!
+//! DNS-rebinding protection for `HttpServer` and `WebSocketServer`, and CORS
+//! for `HttpServer`. A browser page on a hostile site can resolve its own
+//! hostname to 127.0.0.1, so a server bound to localhost must check `Host`
+//! and `Origin` rather than trust the socket.
+
+use std::net::ToSocketAddrs;
+
+/// Hosts accepted by default when bound to a loopback address
+const LOOPBACK_HOSTS: [&str; 3] = ["localhost", "127.0.0.1", "[::1]"];
+
+/// Request headers browsers may send to the MCP endpoint
+#[cfg(feature = "http")]
+const CORS_ALLOW_HEADERS: &str =
+    "Content-Type, Accept, Authorization, Mcp-Session-Id, Mcp-Protocol-Version";
+
+/// Allow-lists for the `Origin` and `Host` request headers
+///
+/// An unset list means "not configured": when bound to a loopback address
+/// it defaults to localhost only, otherwise anything is accepted. Entries
+/// without a port match any port, and `*` matches everything.
+#[derive(Clone, Debug, Default)]
+pub(crate) struct OriginPolicy {
+    origins: Option<Vec<String>>,
+    hosts: Option<Vec<String>>,
+}
+
+impl OriginPolicy {
+    pub(crate) fn set_origins(&mut self, origins: Vec<String>) {
+        self.origins = Some(origins);
+    }
+
+    pub(crate) fn set_hosts(&mut self, hosts: Vec<String>) {
+        self.hosts = Some(hosts);
+    }
+
+    /// True if origins were configured as a list that doesn't include `*`
+    #[cfg(feature = "http")]
+    pub(crate) fn lists_origins(&self) -> bool {
+        self.origins
+            .as_ref()
+            .is_some_and(|origins| !origins.iter().any(|o| o == "*"))
+    }
+
+    /// Fill in localhost-only defaults when `addr` is a loopback address
+    pub(crate) fn for_addr(&self, addr: &str) -> Self {
+        if !is_loopback(addr) {
+            return self.clone();
+        }
+
+        let loopback = || LOOPBACK_HOSTS.iter().map(|h| h.to_string()).collect();
+        let origins = self.origins.clone().unwrap_or_else(|| {
+            LOOPBACK_HOSTS
+                .iter()
+                .flat_map(|h| [format!("http://{}", h), format!("https://{}", h)])
+                .collect()
+        });
+
+        Self {
+            origins: Some(origins),
+            hosts: Some(self.hosts.clone().unwrap_or_else(loopback)),
+        }
+    }
+
+    /// Check request headers, returning the reason for a rejection
+    ///
+    /// Requests without `Origin` (non-browser clients) are only held to the
+    /// host check.
+    pub(crate) fn check(&self, origin: Option<&str>, host: Option<&str>) -> Result<(), String> {
+        if let Some(hosts) = &self.hosts {
+            match host {
+                Some(host) if hosts.iter().any(|allowed| host_matches(allowed, host)) => {}
+                Some(host) => return Err(format!("Host not allowed: {}", host)),
+                None => return Err("Missing Host header".into()),
+            }
+        }
+
+        if let (Some(origins), Some(origin)) = (&self.origins, origin) {
+            if !origins
+                .iter()
+                .any(|allowed| origin_matches(allowed, origin))
+            {
+                return Err(format!("Origin not allowed: {}", origin));
+            }
+        }
+
+        Ok(())
+    }
+}
+
+/// True if every address `addr` resolves to is a loopback address
+fn is_loopback(addr: &str) -> bool {
+    addr.to_socket_addrs()
+        .map(|mut addrs| addrs.all(|a| a.ip().is_loopback()))
+        .unwrap_or(false)
+}
+
+/// Split "host:port" into host and optional port, keeping IPv6 brackets
+fn split_port(authority: &str) -> (&str, Option<&str>) {
+    match authority.rsplit_once(':') {
+        Some((host, port))
+            if !port.is_empty()
+                && port.bytes().all(|b| b.is_ascii_digit())
+                && (!host.starts_with('[') || host.ends_with(']')) =>
+        {
+            (host, Some(port))
+        }
+        _ => (authority, None),
+    }
+}
+
+/// Match a `Host` header against an allow-list entry
+fn host_matches(allowed: &str, host: &str) -> bool {
+    if allowed == "*" {
+        return true;
+    }
+    let (allowed_host, allowed_port) = split_port(allowed);
+    let (host, port) = split_port(host);
+    allowed_host.eq_ignore_ascii_case(host) && (allowed_port.is_none() || allowed_port == port)
+}
+
+/// Match an `Origin` header (`scheme://host[:port]`) against an allow-list entry
+fn origin_matches(allowed: &str, origin: &str) -> bool {
+    if allowed == "*" || allowed.eq_ignore_ascii_case(origin) {
+        return true;
+    }
+    match (allowed.split_once("://"), origin.split_once("://")) {
+        (Some((allowed_scheme, allowed_host)), Some((scheme, host))) => {
+            allowed_scheme.eq_ignore_ascii_case(scheme) && host_matches(allowed_host, host)
+        }
+        _ => false,
+    }
+}
+
+/// CORS settings for browser clients
+///
+/// Origins that pass the server's origin check are echoed back in
+/// `Access-Control-Allow-Origin`, and `Mcp-Session-Id` is exposed so
+/// scripts can read it.
+///
+/// # Example
+/// ```ignore
+/// HttpServer::new(config)
+///     .allowed_origins(["https://app.example.com"])
+///     .cors(Cors::default().max_age(600))
+/// ```
+#[cfg(feature = "http")]
+#[derive(Clone, Debug)]
+pub struct Cors {
+    allow_headers: String,
+    max_age: Option<u32>,
+    allow_credentials: bool,
+}
+
+#[cfg(feature = "http")]
+impl Default for Cors {
+    fn default() -> Self {
+        Self {
+            allow_headers: CORS_ALLOW_HEADERS.to_string(),
+            max_age: None,
+            allow_credentials: false,
+        }
+    }
+}
+
+#[cfg(feature = "http")]
+impl Cors {
+    /// Allow extra request headers in preflight responses
+    pub fn allow_header(mut self, header: &str) -> Self {
+        self.allow_headers.push_str(", ");
+        self.allow_headers.push_str(header);
+        self
+    }
+
+    /// Let browsers cache preflight results for `seconds`
+    pub fn max_age(mut self, seconds: u32) -> Self {
+        self.max_age = Some(seconds);
+        self
+    }
+
+    /// Allow credentialed requests (cookies, HTTP auth)
+    ///
+    /// Any allowed origin is echoed back, so with credentials the server
+    /// refuses to start unless `allowed_origins` lists origins explicitly
+    /// and doesn't include `*`.
+    pub fn allow_credentials(mut self, allow: bool) -> Self {
+        self.allow_credentials = allow;
+        self
+    }
+
+    pub(crate) fn credentials(&self) -> bool {
+        self.allow_credentials
+    }
+
+    /// Headers for any response to a request from `origin`
+    pub(crate) fn response_headers(&self, origin: &str) -> Vec<(&'static str, String)> {
+        let mut headers = vec![
+            ("Access-Control-Allow-Origin", origin.to_string()),
+            (
+                "Access-Control-Expose-Headers",
+                "Mcp-Session-Id".to_string(),
+            ),
+            ("Vary", "Origin".to_string()),
+        ];
+        if self.allow_credentials {
+            headers.push(("Access-Control-Allow-Credentials", "true".to_string()));
+        }
+        headers
+    }
+
+    /// Extra headers for a preflight (`OPTIONS`) response
+    pub(crate) fn preflight_headers(&self) -> Vec<(&'static str, String)> {
+        let mut headers = vec![
+            (
+                "Access-Control-Allow-Methods",
+                "POST, DELETE, OPTIONS".to_string(),
+            ),
+            ("Access-Control-Allow-Headers", self.allow_headers.clone()),
+        ];
+        if let Some(max_age) = self.max_age {
+            headers.push(("Access-Control-Max-Age", max_age.to_string()));
+        }
+        headers
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_loopback_defaults() {
+        let policy = OriginPolicy::default().for_addr("127.0.0.1:3000");
+
+        assert!(policy.check(None, Some("localhost:3000")).is_ok());
+        assert!(policy.check(None, Some("127.0.0.1:3000")).is_ok());
+        assert!(
+            policy
+                .check(Some("http://localhost:5173"), Some("localhost:3000"))
+                .is_ok()
+        );
+
+        // DNS rebinding: attacker's hostname pointing at 127.0.0.1
+        assert!(policy.check(None, Some("evil.example:3000")).is_err());
+        assert!(
+            policy
+                .check(Some("http://evil.example"), Some("localhost:3000"))
+                .is_err()
+        );
+        assert!(policy.check(None, None).is_err());
+    }
+
+    #[test]
+    fn test_public_bind_is_permissive_unless_configured() {
+        let policy = OriginPolicy::default().for_addr("0.0.0.0:3000");
+        assert!(
+            policy
+                .check(Some("https://anything.example"), Some("x"))
+                .is_ok()
+        );
+
+        let mut policy = OriginPolicy::default();
+        policy.set_origins(vec!["https://app.example.com".into()]);
+        let policy = policy.for_addr("0.0.0.0:3000");
+        assert!(
+            policy
+                .check(Some("https://app.example.com"), Some("x"))
+                .is_ok()
+        );
+        assert!(
+            policy
+                .check(Some("http://app.example.com"), Some("x"))
+                .is_err()
+        );
+        assert!(policy.check(None, Some("x")).is_ok());
+    }
+
+    #[test]
+    fn test_configured_lists_replace_loopback_defaults() {
+        let mut policy = OriginPolicy::default();
+        policy.set_hosts(vec!["mcp.internal:8080".into()]);
+        let policy = policy.for_addr("localhost:8080");
+
+        assert!(policy.check(None, Some("mcp.internal:8080")).is_ok());
+        assert!(policy.check(None, Some("mcp.internal:9090")).is_err());
+        assert!(policy.check(None, Some("localhost:8080")).is_err());
+        // Origins still default to localhost
+        assert!(
+            policy
+                .check(Some("https://mcp.internal"), Some("mcp.internal:8080"))
+                .is_err()
+        );
+    }
+
+    #[cfg(feature = "http")]
+    #[test]
+    fn test_lists_origins() {
+        assert!(!OriginPolicy::default().lists_origins());
+
+        let mut policy = OriginPolicy::default();
+        policy.set_origins(vec!["https://app.example.com".into()]);
+        assert!(policy.lists_origins());
+
+        policy.set_origins(vec!["https://app.example.com".into(), "*".into()]);
+        assert!(!policy.lists_origins());
+    }
+
+    #[test]
+    fn test_host_and_origin_matching() {
+        assert!(host_matches("[::1]", "[::1]:3000"));
+        assert!(host_matches("LOCALHOST", "localhost"));
+        assert!(!host_matches("localhost:3000", "localhost"));
+        assert!(host_matches("*", "whatever"));
+
+        assert!(origin_matches("http://localhost", "http://localhost:8080"));
+        assert!(!origin_matches(
+            "http://localhost:80",
+            "http://localhost:8080"
+        ));
+        assert!(!origin_matches("http://localhost", "null"));
+        assert!(origin_matches("null", "null"));
+    }
+
+    #[cfg(feature = "http")]
+    #[test]
+    fn test_cors_headers() {
+        let cors = Cors::default()
+            .allow_header("X-Trace")
+            .max_age(600)
+            .allow_credentials(true);
+
+        let headers = cors.response_headers("https://app.example.com");
+        assert!(headers.contains(&(
+            "Access-Control-Allow-Origin",
+            "https://app.example.com".into()
+        )));
+        assert!(headers.contains(&("Access-Control-Expose-Headers", "Mcp-Session-Id".into())));
+        assert!(headers.contains(&("Access-Control-Allow-Credentials", "true".into())));
+
+        let preflight = cors.preflight_headers();
+        assert!(preflight.contains(&("Access-Control-Max-Age", "600".into())));
+        let (_, allowed) = preflight
+            .iter()
+            .find(|(name, _)| *name == "Access-Control-Allow-Headers")
+            .unwrap();
+        assert!(allowed.contains("Mcp-Session-Id"));
+        assert!(allowed.ends_with("X-Trace"));
+    }
+}
@@ -0,0 +1,179 @@
+//! OAuth Protected Resource Metadata
+//!
+//! The discovery document from RFC 9728. MCP clients that get a `401` read
+//! the `resource_metadata` URL from the `WWW-Authenticate` challenge, fetch
+//! this document, and find the authorization server to get a token from.
+
+use serde_json::{Value, json};
+
+/// Well-known path prefix for the metadata document
+const WELL_KNOWN: &str = "/.well-known/oauth-protected-resource";
+
+/// Metadata describing the server as an OAuth 2.0 protected resource
+///
+/// `resource` is the URL clients use to reach the MCP endpoint, and should
+/// match the audience tokens are issued for.
+///
+/// # Example
+/// ```ignore
+/// HttpServer::new(config)
+///     .protected_resource(
+///         ProtectedResource::new("https://mcp.example.com/mcp")
+///             .authorization_server("https://auth.example.com")
+///             .scopes_supported(&["files:read", "files:write"]),
+///     )
+/// ```
+#[derive(Clone, Debug)]
+pub struct ProtectedResource {
+    resource: String,
+    authorization_servers: Vec<String>,
+    scopes_supported: Vec<String>,
+    resource_name: Option<String>,
+    resource_documentation: Option<String>,
+}
+
+impl ProtectedResource {
+    /// Describe the resource identified by the absolute URL `resource`
+    pub fn new(resource: impl Into<String>) -> Self {
+        Self {
+            resource: resource.into(),
+            authorization_servers: Vec::new(),
+            scopes_supported: Vec::new(),
+            resource_name: None,
+            resource_documentation: None,
+        }
+    }
+
+    /// Add the issuer URL of an authorization server that grants tokens for us
+    pub fn authorization_server(mut self, issuer: impl Into<String>) -> Self {
+        self.authorization_servers.push(issuer.into());
+        self
+    }
+
+    /// Scopes clients can request for this resource
+    pub fn scopes_supported(mut self, scopes: &[&str]) -> Self {
+        self.scopes_supported = scopes.iter().map(|s| s.to_string()).collect();
+        self
+    }
+
+    /// Human-readable name shown to users by clients
+    pub fn resource_name(mut self, name: impl Into<String>) -> Self {
+        self.resource_name = Some(name.into());
+        self
+    }
+
+    /// URL of developer documentation for the resource
+    pub fn resource_documentation(mut self, url: impl Into<String>) -> Self {
+        self.resource_documentation = Some(url.into());
+        self
+    }
+
+    /// Split `resource` into its origin and path, dropping query and fragment
+    fn parts(&self) -> (&str, &str) {
+        let url = self.resource.split(['?', '#']).next().unwrap_or_default();
+        let authority = url.find("://").map_or(0, |i| i + 3);
+        match url[authority..].find('/') {
+            Some(i) => url.split_at(authority + i),
+            None => (url, ""),
+        }
+    }
+
+    /// Path of the metadata document: the well-known prefix plus the resource path
+    pub(crate) fn path(&self) -> String {
+        let path = self.parts().1.trim_end_matches('/');
+        format!("{}{}", WELL_KNOWN, path)
+    }
+
+    /// Absolute URL of the metadata document, for `WWW-Authenticate` challenges
+    pub(crate) fn url(&self) -> String {
+        format!("{}{}", self.parts().0, self.path())
+    }
+
+    /// True if `path` serves the metadata document
+    ///
+    /// Besides the path-specific location, the bare well-known path is
+    /// answered for clients that don't insert the resource path.
+    pub(crate) fn matches(&self, path: &str) -> bool {
+        path == WELL_KNOWN || path == self.path()
+    }
+
+    /// The JSON metadata document
+    pub(crate) fn document(&self) -> Value {
+        let mut document = json!({
+            "resource": self.resource,
+            "authorization_servers": self.authorization_servers,
+            "bearer_methods_supported": ["header"],
+        });
+        if !self.scopes_supported.is_empty() {
+            document["scopes_supported"] = json!(self.scopes_supported);
+        }
+        if let Some(name) = &self.resource_name {
+            document["resource_name"] = json!(name);
+        }
+        if let Some(url) = &self.resource_documentation {
+            document["resource_documentation"] = json!(url);
+        }
+        document
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_metadata_location() {
+        let resource = ProtectedResource::new("https://mcp.example.com/tools/mcp?v=1");
+        assert_eq!(
+            resource.path(),
+            "/.well-known/oauth-protected-resource/tools/mcp"
+        );
+        assert_eq!(
+            resource.url(),
+            "https://mcp.example.com/.well-known/oauth-protected-resource/tools/mcp"
+        );
+        assert!(resource.matches("/.well-known/oauth-protected-resource"));
+        assert!(resource.matches("/.well-known/oauth-protected-resource/tools/mcp"));
+        assert!(!resource.matches("/.well-known/oauth-protected-resource/other"));
+
+        for root in ["https://mcp.example.com", "https://mcp.example.com/"] {
+            let resource = ProtectedResource::new(root);
+            assert_eq!(resource.path(), "/.well-known/oauth-protected-resource");
+            assert_eq!(
+                resource.url(),
+                "https://mcp.example.com/.well-known/oauth-protected-resource"
+            );
+        }
+    }
+
+    #[test]
+    fn test_metadata_document() {
+        let document = ProtectedResource::new("https://mcp.example.com/mcp").document();
+        assert_eq!(document["resource"], "https://mcp.example.com/mcp");
+        assert_eq!(document["authorization_servers"], json!([]));
+        assert_eq!(document["bearer_methods_supported"], json!(["header"]));
+        assert!(document.get("scopes_supported").is_none());
+        assert!(document.get("resource_name").is_none());
+
+        let document = ProtectedResource::new("https://mcp.example.com/mcp")
+            .authorization_server("https://auth.example.com")
+            .authorization_server("https://backup.example.com")
+            .scopes_supported(&["files:read", "files:write"])
+            .resource_name("Files")
+            .resource_documentation("https://example.com/docs")
+            .document();
+        assert_eq!(
+            document["authorization_servers"],
+            json!(["https://auth.example.com", "https://backup.example.com"])
+        );
+        assert_eq!(
+            document["scopes_supported"],
+            json!(["files:read", "files:write"])
+        );
+        assert_eq!(document["resource_name"], "Files");
+        assert_eq!(
+            document["resource_documentation"],
+            "https://example.com/docs"
+        );
+    }
+}
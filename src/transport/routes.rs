@@ -0,0 +1,232 @@
+//! Extra Routes
+//!
+//! Plain HTTP handlers that `HttpServer` serves next to the MCP endpoint,
+//! for things like OAuth callbacks, static docs or webhooks.
+
+use serde_json::Value;
+use std::sync::Arc;
+
+#[cfg(feature = "auth")]
+use crate::auth::Claims;
+
+/// A request to an extra route, with the body already read
+#[derive(Debug, Clone)]
+pub struct RouteRequest {
+    /// Upper-case HTTP method, e.g. `GET`
+    pub method: String,
+    /// Path without the query string
+    pub path: String,
+    /// Raw query string (after `?`), if any
+    pub query: Option<String>,
+    pub headers: Vec<(String, String)>,
+    pub body: Vec<u8>,
+    /// The caller's claims when served with `serve_with_auth`
+    #[cfg(feature = "auth")]
+    pub claims: Option<Claims>,
+}
+
+impl RouteRequest {
+    /// Find a header by name (case-insensitive)
+    pub fn header(&self, name: &str) -> Option<&str> {
+        self.headers
+            .iter()
+            .find(|(n, _)| n.eq_ignore_ascii_case(name))
+            .map(|(_, v)| v.as_str())
+    }
+
+    /// Percent-decoded value of the first query parameter called `name`
+    pub fn query_param(&self, name: &str) -> Option<String> {
+        self.query.as_deref()?.split('&').find_map(|pair| {
+            let (key, value) = pair.split_once('=').unwrap_or((pair, ""));
+            (percent_decode(key) == name).then(|| percent_decode(value))
+        })
+    }
+
+    /// The body as UTF-8 text, if it is valid UTF-8
+    pub fn text(&self) -> Option<&str> {
+        std::str::from_utf8(&self.body).ok()
+    }
+}
+
+/// The response from an extra route
+#[derive(Debug, Clone, PartialEq)]
+pub struct RouteResponse {
+    pub status: u16,
+    pub headers: Vec<(String, String)>,
+    pub body: Vec<u8>,
+}
+
+impl RouteResponse {
+    /// Empty response with the given status
+    pub fn new(status: u16) -> Self {
+        Self {
+            status,
+            headers: Vec::new(),
+            body: Vec::new(),
+        }
+    }
+
+    /// `200` with a `text/plain` body
+    pub fn text(body: impl Into<String>) -> Self {
+        Self::new(200)
+            .with_header("Content-Type", "text/plain; charset=utf-8")
+            .with_body(body.into())
+    }
+
+    /// `200` with a JSON body
+    pub fn json(value: &Value) -> Self {
+        Self::new(200)
+            .with_header("Content-Type", "application/json")
+            .with_body(value.to_string())
+    }
+
+    /// Replace the status code
+    pub fn with_status(mut self, status: u16) -> Self {
+        self.status = status;
+        self
+    }
+
+    /// Add a header
+    pub fn with_header(mut self, name: impl Into<String>, value: impl Into<String>) -> Self {
+        self.headers.push((name.into(), value.into()));
+        self
+    }
+
+    /// Replace the body
+    pub fn with_body(mut self, body: impl Into<Vec<u8>>) -> Self {
+        self.body = body.into();
+        self
+    }
+}
+
+/// Handler for an extra route
+pub(crate) type RouteHandler = dyn Fn(&RouteRequest) -> RouteResponse + Send + Sync;
+
+struct Route {
+    method: String,
+    path: String,
+    handler: Arc<RouteHandler>,
+}
+
+/// Extra routes keyed by method and path
+#[derive(Default)]
+pub(crate) struct Routes {
+    routes: Vec<Route>,
+}
+
+impl Routes {
+    /// Register `handler`, replacing any earlier one for the same method and path
+    pub(crate) fn add(&mut self, method: &str, path: String, handler: Arc<RouteHandler>) {
+        let method = method.to_ascii_uppercase();
+        self.routes
+            .retain(|route| route.method != method || route.path != path);
+        self.routes.push(Route {
+            method,
+            path,
+            handler,
+        });
+    }
+
+    /// True if any handler is registered for `path`
+    pub(crate) fn has_path(&self, path: &str) -> bool {
+        self.routes.iter().any(|route| route.path == path)
+    }
+
+    /// The handler for `method` on `path`, or the methods `path` does allow
+    pub(crate) fn find(
+        &self,
+        method: &str,
+        path: &str,
+    ) -> std::result::Result<&RouteHandler, Vec<&str>> {
+        let mut allowed = Vec::new();
+        for route in self.routes.iter().filter(|route| route.path == path) {
+            if route.method == method {
+                return Ok(route.handler.as_ref());
+            }
+            allowed.push(route.method.as_str());
+        }
+        Err(allowed)
+    }
+}
+
+/// Split a request URL into path and query
+pub(crate) fn split_url(url: &str) -> (&str, Option<&str>) {
+    match url.split_once('?') {
+        Some((path, query)) => (path, Some(query)),
+        None => (url, None),
+    }
+}
+
+/// Decode `%XX` escapes and `+` (as space) in a query component
+fn percent_decode(s: &str) -> String {
+    let bytes = s.as_bytes();
+    let mut out = Vec::with_capacity(bytes.len());
+    let mut i = 0;
+    while i < bytes.len() {
+        let escaped = match bytes.get(i + 1..i + 3) {
+            Some(&[hi, lo]) if bytes[i] == b'%' => hex(hi).zip(hex(lo)),
+            _ => None,
+        };
+        match (escaped, bytes[i]) {
+            (Some((hi, lo)), _) => {
+                out.push(hi << 4 | lo);
+                i += 3;
+                continue;
+            }
+            (None, b'+') => out.push(b' '),
+            (None, byte) => out.push(byte),
+        }
+        i += 1;
+    }
+    String::from_utf8_lossy(&out).into_owned()
+}
+
+fn hex(digit: u8) -> Option<u8> {
+    (digit as char).to_digit(16).map(|d| d as u8)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn request(query: Option<&str>) -> RouteRequest {
+        RouteRequest {
+            method: "GET".into(),
+            path: "/callback".into(),
+            query: query.map(String::from),
+            headers: vec![("X-Hub-Signature".into(), "abc".into())],
+            body: b"hello".to_vec(),
+            #[cfg(feature = "auth")]
+            claims: None,
+        }
+    }
+
+    #[test]
+    fn test_route_request_accessors() {
+        let request = request(Some("code=a%2Fb+c&state=xyz&flag&bad=%zz"));
+        assert_eq!(request.query_param("code").as_deref(), Some("a/b c"));
+        assert_eq!(request.query_param("state").as_deref(), Some("xyz"));
+        assert_eq!(request.query_param("flag").as_deref(), Some(""));
+        assert_eq!(request.query_param("bad").as_deref(), Some("%zz"));
+        assert_eq!(request.query_param("missing"), None);
+        assert_eq!(request.header("x-hub-signature"), Some("abc"));
+        assert_eq!(request.text(), Some("hello"));
+    }
+
+    #[test]
+    fn test_routes_lookup() {
+        let mut routes = Routes::default();
+        let ok: Arc<RouteHandler> = Arc::new(|_| RouteResponse::new(200));
+        let created: Arc<RouteHandler> = Arc::new(|_| RouteResponse::new(201));
+        routes.add("get", "/docs".into(), ok.clone());
+        routes.add("POST", "/docs".into(), ok);
+        routes.add("POST", "/docs".into(), created);
+
+        assert!(routes.has_path("/docs"));
+        assert!(!routes.has_path("/other"));
+        let handler = routes.find("POST", "/docs").ok().unwrap();
+        assert_eq!(handler(&request(None)).status, 201);
+        assert_eq!(routes.find("PUT", "/docs").err(), Some(vec!["GET", "POST"]));
+        assert_eq!(split_url("/cb?x=1"), ("/cb", Some("x=1")));
+    }
+}
@@ -0,0 +1,184 @@
+//! HTTP Session Store
+//!
+//! Sessions for `HttpServer`, keyed by `Mcp-Session-Id`. Each remembers
+//! the principal that created it and when it was last used, so idle
+//! sessions expire, the store stays bounded, and one caller can't pick up
+//! another's session by learning its id.
+
+use crate::server::Session;
+use std::collections::HashMap;
+use std::time::{Duration, Instant};
+
+/// Sessions unused for this long are dropped
+pub(crate) const DEFAULT_IDLE_TIMEOUT: Duration = Duration::from_secs(30 * 60);
+
+/// Most sessions kept at once; the least recently used is evicted beyond this
+pub(crate) const DEFAULT_MAX_SESSIONS: usize = 10_000;
+
+/// Most characters of a session id that are logged
+const LOGGED_ID_CHARS: usize = 8;
+
+struct Entry {
+    session: Session,
+    /// Principal that created the session, when authenticated
+    owner: Option<String>,
+    last_used: Instant,
+}
+
+/// Live sessions with idle expiry and a size cap
+pub(crate) struct Sessions {
+    entries: HashMap<String, Entry>,
+    idle_timeout: Duration,
+    max_sessions: usize,
+}
+
+impl Sessions {
+    pub(crate) fn new(idle_timeout: Duration, max_sessions: usize) -> Self {
+        Self {
+            entries: HashMap::new(),
+            idle_timeout,
+            max_sessions: max_sessions.max(1),
+        }
+    }
+
+    pub(crate) fn len(&self) -> usize {
+        self.entries.len()
+    }
+
+    /// The session `id` if it is live and belongs to `owner`
+    ///
+    /// Expired sessions are dropped; another principal's session is
+    /// reported as missing, so ids can't be probed.
+    pub(crate) fn get(&mut self, id: &str, owner: Option<&str>) -> Option<Session> {
+        let entry = self.entries.get_mut(id)?;
+        if entry.last_used.elapsed() >= self.idle_timeout {
+            log::info!("Session expired: {}", short_id(id));
+            self.entries.remove(id);
+            return None;
+        }
+        if entry.owner.as_deref() != owner {
+            log::warn!("Session {} used by another principal", short_id(id));
+            return None;
+        }
+        entry.last_used = Instant::now();
+        Some(entry.session.clone())
+    }
+
+    /// Store `session` under `id`, evicting the least recently used one when full
+    pub(crate) fn insert(&mut self, id: String, session: Session, owner: Option<&str>) {
+        if let Some(entry) = self.entries.get_mut(&id) {
+            entry.session = session;
+            entry.last_used = Instant::now();
+            return;
+        }
+
+        if self.entries.len() >= self.max_sessions {
+            self.remove_expired();
+        }
+        if self.entries.len() >= self.max_sessions {
+            let oldest = self
+                .entries
+                .iter()
+                .min_by_key(|(_, entry)| entry.last_used)
+                .map(|(id, _)| id.clone());
+            if let Some(oldest) = oldest {
+                log::info!("Session limit reached, evicting {}", short_id(&oldest));
+                self.entries.remove(&oldest);
+            }
+        }
+
+        self.entries.insert(
+            id,
+            Entry {
+                session,
+                owner: owner.map(String::from),
+                last_used: Instant::now(),
+            },
+        );
+    }
+
+    /// Remove session `id` if it belongs to `owner`
+    pub(crate) fn remove(&mut self, id: &str, owner: Option<&str>) -> bool {
+        match self.entries.get(id) {
+            Some(entry) if entry.owner.as_deref() == owner => {
+                self.entries.remove(id);
+                true
+            }
+            _ => false,
+        }
+    }
+
+    fn remove_expired(&mut self) {
+        let idle_timeout = self.idle_timeout;
+        self.entries
+            .retain(|_, entry| entry.last_used.elapsed() < idle_timeout);
+    }
+}
+
+/// The start of session `id`, for logs
+///
+/// Session ids work like bearer tokens, so logs get enough of one to match
+/// up lines but never enough to replay it.
+pub(crate) fn short_id(id: &str) -> String {
+    let keep = LOGGED_ID_CHARS.min(id.chars().count() / 4);
+    let prefix: String = id.chars().take(keep).collect();
+    format!("{}...", prefix)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::thread;
+
+    #[test]
+    fn test_session_expiry() {
+        let mut sessions = Sessions::new(Duration::from_millis(50), 10);
+        sessions.insert("a".into(), Session::default(), None);
+        assert!(sessions.get("a", None).is_some());
+
+        thread::sleep(Duration::from_millis(80));
+        assert!(sessions.get("a", None).is_none());
+        assert_eq!(sessions.len(), 0);
+    }
+
+    #[test]
+    fn test_session_cap() {
+        let mut sessions = Sessions::new(DEFAULT_IDLE_TIMEOUT, 3);
+        for id in ["a", "b", "c"] {
+            sessions.insert(id.into(), Session::default(), None);
+            thread::sleep(Duration::from_millis(2));
+        }
+        // Using "a" makes "b" the least recently used
+        assert!(sessions.get("a", None).is_some());
+
+        sessions.insert("d".into(), Session::default(), None);
+        assert!(sessions.get("b", None).is_none());
+        assert!(sessions.get("a", None).is_some());
+
+        for i in 0..100 {
+            sessions.insert(format!("new-{}", i), Session::default(), None);
+        }
+        assert_eq!(sessions.len(), 3);
+    }
+
+    #[test]
+    fn test_short_id() {
+        assert_eq!(short_id("0123456789abcdef0123456789abcdef"), "01234567...");
+        assert_eq!(short_id("abcd"), "a...");
+        assert_eq!(short_id(""), "...");
+    }
+
+    #[test]
+    fn test_session_owner() {
+        let mut sessions = Sessions::new(DEFAULT_IDLE_TIMEOUT, 10);
+        sessions.insert("a".into(), Session::default(), Some("alice"));
+
+        assert!(sessions.get("a", Some("alice")).is_some());
+        assert!(sessions.get("a", Some("bob")).is_none());
+        assert!(sessions.get("a", None).is_none());
+
+        assert!(!sessions.remove("a", Some("bob")));
+        assert!(sessions.remove("a", Some("alice")));
+        assert_eq!(sessions.len(), 0);
+    }
+}
@@ -3,22 +3,40 @@
 //! Communication over stdin/stdout for local MCP servers.
 
 use crate::transport::Transport;
+use crate::transport::stream::{Framing, StreamTransport};
 use crate::types::{JsonRpcMessage, Result};
-use std::io::{self, BufRead, Write};
+use std::io::{self, BufReader};
 
 /// Stdio transport - reads from stdin, writes to stdout
+///
+/// A thin wrapper over `StreamTransport`, so framing options are the same.
 pub struct StdioTransport {
-    stdin: io::Stdin,
-    stdout: io::Stdout,
+    inner: StreamTransport<BufReader<io::Stdin>, io::Stdout>,
 }
 
 impl StdioTransport {
     pub fn new() -> Self {
         Self {
-            stdin: io::stdin(),
-            stdout: io::stdout(),
+            inner: StreamTransport::new(BufReader::new(io::stdin()), io::stdout()),
         }
     }
+
+    /// Set the message framing (default: newline-delimited)
+    pub fn with_framing(mut self, framing: Framing) -> Self {
+        self.inner = self.inner.with_framing(framing);
+        self
+    }
+
+    /// Set the maximum accepted message size in bytes
+    pub fn with_max_message_size(mut self, max: usize) -> Self {
+        self.inner = self.inner.with_max_message_size(max);
+        self
+    }
+
+    /// Framing currently in use (resolved once `Auto` has seen input)
+    pub fn framing(&self) -> Framing {
+        self.inner.framing()
+    }
 }
 
 impl Default for StdioTransport {
@@ -29,28 +47,15 @@ impl Default for StdioTransport {
 
 impl Transport for StdioTransport {
     fn read(&mut self) -> Result<JsonRpcMessage> {
-        let mut line = String::new();
-        self.stdin.lock().read_line(&mut line)?;
-
-        if line.is_empty() {
-            return Err(crate::types::McpError::TransportClosed);
-        }
-
-        let message: JsonRpcMessage = serde_json::from_str(&line)?;
-        Ok(message)
+        self.inner.read()
     }
 
     fn write(&mut self, message: &JsonRpcMessage) -> Result<()> {
-        let mut handle = self.stdout.lock();
-        serde_json::to_writer(&mut handle, message)?;
-        writeln!(handle)?;
-        handle.flush()?;
-        Ok(())
+        self.inner.write(message)
     }
 
     fn close(&mut self) -> Result<()> {
-        // Nothing to close for stdio
-        Ok(())
+        self.inner.close()
     }
 }
 
@@ -87,7 +92,12 @@ mod tests {
         assert!(transport.close().is_ok());
     }
 
+    #[test]
+    fn test_stdio_transport_builder() {
+        let transport = StdioTransport::new().with_framing(Framing::ContentLength);
+        assert_eq!(transport.framing(), Framing::ContentLength);
+    }
+
     // Note: We can't easily test read() and write() with real stdin/stdout in unit tests.
-    // Those are covered by integration tests (running the examples).
-    // The HTTP transport tests provide coverage for the Transport trait methods.
+    // StreamTransport covers the same code paths against in-memory buffers.
 }
@@ -0,0 +1,481 @@
+//! Stream Transport
+//!
+//! Communication over any `BufRead` + `Write` pair - pipes, sockets, or
+//! in-memory buffers in tests.
+//!
+//! Supports the newline-delimited JSON framing from the MCP spec and
+//! LSP-style `Content-Length:` header framing used by some embedding hosts.
+
+use crate::transport::{Transport, parse_message};
+use crate::types::{JsonRpcMessage, McpError, Result};
+use std::io::{self, BufRead, Read, Write};
+
+/// Default maximum size of a single message (4 MiB)
+pub const DEFAULT_MAX_MESSAGE_SIZE: usize = 4 * 1024 * 1024;
+
+/// Longest header line we'll buffer before giving up on it
+const MAX_HEADER_LINE: u64 = 1024;
+
+/// How messages are delimited on the byte stream
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum Framing {
+    /// One JSON message per line (MCP stdio spec)
+    #[default]
+    Newline,
+    /// `Content-Length: N` headers, a blank line, then N bytes of JSON (LSP style)
+    ContentLength,
+    /// Detect from the first non-whitespace byte of input
+    ///
+    /// `{` or `[` selects newline framing, anything else header framing.
+    Auto,
+}
+
+/// Stream transport - reads from any `BufRead`, writes to any `Write`
+///
+/// # Example
+/// ```ignore
+/// let stream = UnixStream::connect("/tmp/mcp.sock")?;
+/// let transport = StreamTransport::new(BufReader::new(stream.try_clone()?), stream);
+/// server.start(transport, context)?;
+/// ```
+pub struct StreamTransport<R, W> {
+    reader: R,
+    writer: W,
+    framing: Framing,
+    max_message_size: usize,
+}
+
+impl<R: BufRead, W: Write> StreamTransport<R, W> {
+    /// Create a transport over the given reader and writer
+    pub fn new(reader: R, writer: W) -> Self {
+        Self {
+            reader,
+            writer,
+            framing: Framing::default(),
+            max_message_size: DEFAULT_MAX_MESSAGE_SIZE,
+        }
+    }
+
+    /// Set the message framing (default: newline-delimited)
+    pub fn with_framing(mut self, framing: Framing) -> Self {
+        self.framing = framing;
+        self
+    }
+
+    /// Set the maximum accepted message size in bytes
+    ///
+    /// Larger messages are discarded without being buffered and reported
+    /// as `McpError::MessageTooLarge`.
+    pub fn with_max_message_size(mut self, max: usize) -> Self {
+        self.max_message_size = max;
+        self
+    }
+
+    /// Framing currently in use (resolved once `Auto` has seen input)
+    pub fn framing(&self) -> Framing {
+        self.framing
+    }
+
+    /// Get the underlying writer (e.g. to inspect an in-memory buffer)
+    pub fn writer(&self) -> &W {
+        &self.writer
+    }
+
+    /// Consume the transport, returning the reader and writer
+    pub fn into_inner(self) -> (R, W) {
+        (self.reader, self.writer)
+    }
+}
+
+impl<R, W> Transport for StreamTransport<R, W>
+where
+    R: BufRead + Send + Sync,
+    W: Write + Send + Sync,
+{
+    fn read(&mut self) -> Result<JsonRpcMessage> {
+        if self.framing == Framing::Auto {
+            self.framing = detect_framing(&mut self.reader)?;
+        }
+
+        let body = match self.framing {
+            Framing::ContentLength => read_framed(&mut self.reader, self.max_message_size)?,
+            _ => read_line(&mut self.reader, self.max_message_size)?,
+        };
+
+        parse_message(&body)
+    }
+
+    fn write(&mut self, message: &JsonRpcMessage) -> Result<()> {
+        write_message(&mut self.writer, message, self.framing)
+    }
+
+    fn close(&mut self) -> Result<()> {
+        self.writer.flush()?;
+        Ok(())
+    }
+}
+
+/// Peek at the input to pick a framing, consuming leading whitespace
+fn detect_framing(reader: &mut impl BufRead) -> Result<Framing> {
+    loop {
+        let buf = reader.fill_buf()?;
+        if buf.is_empty() {
+            return Err(McpError::TransportClosed);
+        }
+
+        let skip = buf.iter().take_while(|b| b.is_ascii_whitespace()).count();
+        if skip < buf.len() {
+            let framing = match buf[skip] {
+                b'{' | b'[' => Framing::Newline,
+                _ => Framing::ContentLength,
+            };
+            reader.consume(skip);
+            return Ok(framing);
+        }
+        reader.consume(skip);
+    }
+}
+
+/// Read one newline-delimited message, skipping blank lines
+///
+/// Lines longer than `max_size` are discarded up to the next newline.
+fn read_line(reader: &mut impl BufRead, max_size: usize) -> Result<String> {
+    loop {
+        let mut line = Vec::new();
+        let read = reader
+            .by_ref()
+            .take(max_size as u64 + 1)
+            .read_until(b'\n', &mut line)?;
+
+        if read == 0 {
+            return Err(McpError::TransportClosed);
+        }
+
+        if line.last() != Some(&b'\n') && line.len() > max_size {
+            reader.skip_until(b'\n')?;
+            return Err(McpError::MessageTooLarge(max_size));
+        }
+
+        let line = String::from_utf8(line)
+            .map_err(|_| McpError::InvalidMessage("Message is not valid UTF-8".into()))?;
+
+        if !line.trim().is_empty() {
+            return Ok(line);
+        }
+    }
+}
+
+/// Read one `Content-Length` framed message
+///
+/// Lines that aren't headers are dropped and header parsing restarts, so
+/// stray output on the pipe doesn't desynchronize the stream. Blocks with a
+/// missing or invalid length are skipped; bodies over `max_size` are
+/// discarded and reported as `McpError::MessageTooLarge`.
+fn read_framed(reader: &mut impl BufRead, max_size: usize) -> Result<String> {
+    loop {
+        let Some(length) = read_headers(reader)? else {
+            continue;
+        };
+
+        if length > max_size {
+            io::copy(&mut reader.by_ref().take(length as u64), &mut io::sink())?;
+            return Err(McpError::MessageTooLarge(max_size));
+        }
+
+        let mut body = vec![0; length];
+        reader.read_exact(&mut body).map_err(|e| match e.kind() {
+            io::ErrorKind::UnexpectedEof => McpError::TransportClosed,
+            _ => McpError::Io(e),
+        })?;
+
+        return String::from_utf8(body)
+            .map_err(|_| McpError::InvalidMessage("Message body is not valid UTF-8".into()));
+    }
+}
+
+/// Read a header block up to the blank line
+///
+/// Returns the content length, or `None` if the block was unusable.
+fn read_headers(reader: &mut impl BufRead) -> Result<Option<usize>> {
+    let mut length = None;
+    let mut valid = true;
+    let mut seen_header = false;
+
+    loop {
+        let mut line = String::new();
+        match reader.by_ref().take(MAX_HEADER_LINE).read_line(&mut line) {
+            Ok(0) => return Err(McpError::TransportClosed),
+            Ok(_) => {}
+            // Not UTF-8 - can't be a header, start over
+            Err(e) if e.kind() == io::ErrorKind::InvalidData => {
+                seen_header = false;
+                length = None;
+                valid = true;
+                continue;
+            }
+            Err(e) => return Err(e.into()),
+        }
+
+        let line = line.trim_end_matches(['\r', '\n']);
+        if line.is_empty() {
+            if !seen_header {
+                // Blank lines between messages
+                continue;
+            }
+            return Ok(if valid { length } else { None });
+        }
+
+        match parse_header(line) {
+            Some((name, value)) => {
+                seen_header = true;
+                if name.eq_ignore_ascii_case("Content-Length") {
+                    match value.parse() {
+                        Ok(n) => length = Some(n),
+                        Err(_) => valid = false,
+                    }
+                }
+            }
+            None => {
+                // Not a header - resync by starting a fresh block
+                log::warn!("Skipping unexpected input ({} bytes)", line.len());
+                seen_header = false;
+                length = None;
+                valid = true;
+            }
+        }
+    }
+}
+
+/// Split a `Name: value` header line
+fn parse_header(line: &str) -> Option<(&str, &str)> {
+    let (name, value) = line.split_once(':')?;
+    let is_token = !name.is_empty()
+        && name
+            .bytes()
+            .all(|b| b.is_ascii_alphanumeric() || b == b'-' || b == b'_');
+    is_token.then(|| (name, value.trim()))
+}
+
+/// Write one message using the given framing
+fn write_message(
+    writer: &mut impl Write,
+    message: &JsonRpcMessage,
+    framing: Framing,
+) -> Result<()> {
+    let json = serde_json::to_string(message)?;
+    match framing {
+        Framing::ContentLength => write!(writer, "Content-Length: {}\r\n\r\n{}", json.len(), json)?,
+        _ => writeln!(writer, "{}", json)?,
+    }
+    writer.flush()?;
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::io::Cursor;
+
+    fn framed(body: &str) -> String {
+        format!("Content-Length: {}\r\n\r\n{}", body.len(), body)
+    }
+
+    #[test]
+    fn test_stream_transport_builder() {
+        let transport = StreamTransport::new(Cursor::new(""), Vec::new())
+            .with_framing(Framing::ContentLength)
+            .with_max_message_size(1024);
+        assert_eq!(transport.framing(), Framing::ContentLength);
+        assert_eq!(transport.max_message_size, 1024);
+    }
+
+    #[test]
+    fn test_stream_transport_read_write() {
+        let input = "{\"jsonrpc\":\"2.0\",\"id\":1,\"method\":\"ping\"}\n";
+        let mut transport = StreamTransport::new(Cursor::new(input), Vec::new());
+
+        let msg = transport.read().unwrap();
+        assert_eq!(msg, JsonRpcMessage::request(1i64, "ping", None));
+        assert!(matches!(transport.read(), Err(McpError::TransportClosed)));
+
+        let response = JsonRpcMessage::response(1i64, serde_json::json!({}));
+        transport.write(&response).unwrap();
+        transport.close().unwrap();
+
+        let (_, out) = transport.into_inner();
+        let expected = format!("{}\n", serde_json::to_string(&response).unwrap());
+        assert_eq!(String::from_utf8(out).unwrap(), expected);
+    }
+
+    #[test]
+    fn test_stream_transport_auto_framing_replies_in_kind() {
+        let body = r#"{"jsonrpc":"2.0","id":1,"method":"ping"}"#;
+        let mut transport =
+            StreamTransport::new(Cursor::new(framed(body)), Vec::new()).with_framing(Framing::Auto);
+
+        transport.read().unwrap();
+        assert_eq!(transport.framing(), Framing::ContentLength);
+
+        let response = JsonRpcMessage::response(1i64, serde_json::json!({}));
+        transport.write(&response).unwrap();
+        let written = String::from_utf8(transport.writer().clone()).unwrap();
+        assert!(written.starts_with("Content-Length: "));
+    }
+
+    #[test]
+    fn test_read_framed_messages() {
+        let a = r#"{"jsonrpc":"2.0","id":1,"method":"ping"}"#;
+        let b = r#"{"jsonrpc":"2.0","method":"notifications/initialized"}"#;
+        let input = format!(
+            "{}Content-Type: application/vscode-jsonrpc; charset=utf-8\r\n{}",
+            framed(a),
+            framed(b)
+        );
+        let mut reader = Cursor::new(input);
+
+        assert_eq!(read_framed(&mut reader, 1024).unwrap(), a);
+        assert_eq!(read_framed(&mut reader, 1024).unwrap(), b);
+        assert!(matches!(
+            read_framed(&mut reader, 1024),
+            Err(McpError::TransportClosed)
+        ));
+    }
+
+    #[test]
+    fn test_read_framed_header_case_insensitive() {
+        let body = r#"{"jsonrpc":"2.0","id":1,"method":"ping"}"#;
+        let input = format!("content-length: {}\n\n{}", body.len(), body);
+        let mut reader = Cursor::new(input);
+        assert_eq!(read_framed(&mut reader, 1024).unwrap(), body);
+    }
+
+    #[test]
+    fn test_read_framed_resyncs_after_garbage() {
+        let body = r#"{"jsonrpc":"2.0","id":1,"method":"ping"}"#;
+        let input = format!("warning from host!\n{{not json}}\n{}", framed(body));
+        let mut reader = Cursor::new(input);
+        assert_eq!(read_framed(&mut reader, 1024).unwrap(), body);
+    }
+
+    #[test]
+    fn test_read_framed_skips_bad_length() {
+        let body = r#"{"jsonrpc":"2.0","id":1,"method":"ping"}"#;
+        let input = format!(
+            "Content-Length: lots\r\n\r\nX-Other: 1\r\n\r\n{}",
+            framed(body)
+        );
+        let mut reader = Cursor::new(input);
+        assert_eq!(read_framed(&mut reader, 1024).unwrap(), body);
+    }
+
+    #[test]
+    fn test_read_framed_discards_oversized() {
+        let big = format!(
+            r#"{{"jsonrpc":"2.0","id":1,"method":"{}"}}"#,
+            "x".repeat(200)
+        );
+        let small = r#"{"jsonrpc":"2.0","id":2,"method":"ping"}"#;
+        let input = format!("{}{}", framed(&big), framed(small));
+        let mut reader = Cursor::new(input);
+        assert!(matches!(
+            read_framed(&mut reader, 100),
+            Err(McpError::MessageTooLarge(100))
+        ));
+        assert_eq!(read_framed(&mut reader, 100).unwrap(), small);
+    }
+
+    #[test]
+    fn test_read_framed_truncated_body() {
+        let mut reader = Cursor::new("Content-Length: 50\r\n\r\n{\"short\"");
+        assert!(matches!(
+            read_framed(&mut reader, 1024),
+            Err(McpError::TransportClosed)
+        ));
+    }
+
+    #[test]
+    fn test_detect_framing() {
+        let mut reader = Cursor::new("\r\n  {\"jsonrpc\":\"2.0\"}\n");
+        assert_eq!(detect_framing(&mut reader).unwrap(), Framing::Newline);
+        // Leading whitespace consumed, message untouched
+        assert_eq!(
+            read_line(&mut reader, 1024).unwrap(),
+            "{\"jsonrpc\":\"2.0\"}\n"
+        );
+
+        let mut reader = Cursor::new("Content-Length: 2\r\n\r\n{}");
+        assert_eq!(detect_framing(&mut reader).unwrap(), Framing::ContentLength);
+
+        let mut reader = Cursor::new("  \n");
+        assert!(matches!(
+            detect_framing(&mut reader),
+            Err(McpError::TransportClosed)
+        ));
+    }
+
+    #[test]
+    fn test_write_message_framing() {
+        let msg = JsonRpcMessage::response(1i64, serde_json::json!({}));
+        let json = serde_json::to_string(&msg).unwrap();
+
+        let mut out = Vec::new();
+        write_message(&mut out, &msg, Framing::Newline).unwrap();
+        assert_eq!(String::from_utf8(out).unwrap(), format!("{}\n", json));
+
+        let mut out = Vec::new();
+        write_message(&mut out, &msg, Framing::ContentLength).unwrap();
+        assert_eq!(String::from_utf8(out).unwrap(), framed(&json));
+    }
+
+    #[test]
+    fn test_read_line_skips_blank_lines() {
+        let mut reader = Cursor::new("\n  \r\n{\"a\":1}\n\n");
+        assert_eq!(read_line(&mut reader, 1024).unwrap(), "{\"a\":1}\n");
+        assert!(matches!(
+            read_line(&mut reader, 1024),
+            Err(McpError::TransportClosed)
+        ));
+    }
+
+    #[test]
+    fn test_read_line_without_trailing_newline() {
+        let mut reader = Cursor::new("{\"a\":1}");
+        assert_eq!(read_line(&mut reader, 1024).unwrap(), "{\"a\":1}");
+    }
+
+    #[test]
+    fn test_read_line_too_long() {
+        let input = format!("{}\n{{\"ok\":true}}\n", "x".repeat(100));
+        let mut reader = Cursor::new(input);
+        assert!(matches!(
+            read_line(&mut reader, 10),
+            Err(McpError::MessageTooLarge(10))
+        ));
+        // Rest of the long line was discarded
+        assert_eq!(read_line(&mut reader, 20).unwrap(), "{\"ok\":true}\n");
+
+        // Exactly at the limit is fine
+        let mut reader = Cursor::new("0123456789\n");
+        assert_eq!(read_line(&mut reader, 10).unwrap(), "0123456789\n");
+    }
+
+    #[test]
+    fn test_read_line_invalid_utf8() {
+        let mut reader = Cursor::new(b"\xff\xfe\n{}\n".to_vec());
+        assert!(matches!(
+            read_line(&mut reader, 1024),
+            Err(McpError::InvalidMessage(_))
+        ));
+        assert_eq!(read_line(&mut reader, 1024).unwrap(), "{}\n");
+    }
+
+    #[test]
+    fn test_parse_message_errors() {
+        assert!(matches!(parse_message("not json"), Err(McpError::Json(_))));
+        assert!(matches!(
+            parse_message(r#"{"hello":"world"}"#),
+            Err(McpError::InvalidMessage(_))
+        ));
+        assert!(parse_message(r#"{"jsonrpc":"2.0","id":1,"method":"ping"}"#).is_ok());
+    }
+}
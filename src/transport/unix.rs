@@ -0,0 +1,541 @@
+//! Unix Socket Transport
+//!
+//! Serves MCP over a Unix domain socket for local daemons. Each connection
+//! runs its own session on a separate thread.
+
+use crate::server::{RegistryHandle, Server, ServerConfig};
+use crate::transport::SetupFn;
+use crate::transport::connections::{Connections, DEFAULT_MAX_CONNECTIONS, ShutdownHandle};
+use crate::transport::stream::{Framing, StreamTransport};
+use crate::types::{McpError, Result};
+use std::fs;
+use std::io::{self, BufReader};
+use std::os::unix::fs::{DirBuilderExt, FileTypeExt, PermissionsExt};
+use std::os::unix::net::{UnixListener, UnixStream};
+use std::path::Path;
+use std::sync::atomic::{AtomicUsize, Ordering};
+use std::thread::{self, JoinHandle};
+
+/// Default socket file permissions (owner read/write only)
+const DEFAULT_SOCKET_MODE: u32 = 0o600;
+
+/// Unix domain socket MCP server
+///
+/// Accepts up to `max_connections` concurrent clients. Each connection gets
+/// its own `Server` and context over the registry `with_tools` built, so
+/// every client goes through its own `initialize` handshake.
+///
+/// # Example
+/// ```ignore
+/// UnixServer::new(config)
+///     .with_tools(|s| {
+///         s.add_tool(EchoTool)?;
+///         Ok(())
+///     })
+///     .serve("/tmp/my-mcp.sock", || AppContext::new())?;
+/// ```
+pub struct UnixServer<C> {
+    config: ServerConfig,
+    setup: Option<SetupFn<C>>,
+    registry: RegistryHandle<C>,
+    framing: Framing,
+    mode: u32,
+    max_connections: usize,
+    shutdown: ShutdownHandle,
+}
+
+impl<C: Send + Sync + 'static> UnixServer<C> {
+    /// Create a new Unix socket server with the given configuration
+    pub fn new(config: ServerConfig) -> Self {
+        Self {
+            config,
+            setup: None,
+            registry: RegistryHandle::default(),
+            framing: Framing::default(),
+            mode: DEFAULT_SOCKET_MODE,
+            max_connections: DEFAULT_MAX_CONNECTIONS,
+            shutdown: ShutdownHandle::default(),
+        }
+    }
+
+    /// Configure tools via a setup closure
+    ///
+    /// The closure runs once when serving starts; every connection then
+    /// shares the registry it built. Use `registry` to change tools while
+    /// serving.
+    pub fn with_tools<F>(mut self, setup: F) -> Self
+    where
+        F: Fn(&mut Server<C>) -> Result<()> + Send + Sync + 'static,
+    {
+        self.setup = Some(Box::new(setup));
+        self
+    }
+
+    /// Get a handle for adding or removing tools, resources and prompts while serving
+    pub fn registry(&self) -> RegistryHandle<C> {
+        self.registry.clone()
+    }
+
+    /// Set the message framing for connections (default: newline-delimited)
+    pub fn framing(mut self, framing: Framing) -> Self {
+        self.framing = framing;
+        self
+    }
+
+    /// Set the socket file permissions (default: 0o600)
+    pub fn permissions(mut self, mode: u32) -> Self {
+        self.mode = mode;
+        self
+    }
+
+    /// Most connections served at once (default: 256)
+    ///
+    /// Connections beyond this are closed as soon as they are accepted.
+    pub fn max_connections(mut self, max: usize) -> Self {
+        self.max_connections = max;
+        self
+    }
+
+    /// Get a handle that can stop the server, before or while it serves
+    pub fn shutdown_handle(&self) -> ShutdownHandle {
+        self.shutdown.clone()
+    }
+
+    /// Bind the socket and serve until shut down
+    ///
+    /// The context factory is called for each new connection. A stale
+    /// socket file left by a dead process is removed; a live one is an error.
+    /// The socket only appears at `path` once its permissions are set.
+    pub fn serve<F>(self, path: impl AsRef<Path>, context_factory: F) -> Result<()>
+    where
+        F: Fn() -> C,
+    {
+        if self.shutdown.is_stopped() {
+            return Ok(());
+        }
+        self.build_registry()?;
+
+        let path = path.as_ref();
+        remove_stale_socket(path)?;
+
+        let listener = bind_private(path, self.mode)?;
+        let wake_path = path.to_path_buf();
+        // Wake the blocking accept() with a throwaway connection
+        let listening = self.shutdown.listening(move || {
+            let _ = UnixStream::connect(&wake_path);
+        });
+        if !listening {
+            let _ = fs::remove_file(path);
+            return Ok(());
+        }
+
+        log::info!(
+            "MCP Unix socket server `{}` listening on {}",
+            self.config.name,
+            path.display()
+        );
+
+        let mut connections = Connections::new(self.max_connections);
+
+        for stream in listener.incoming() {
+            if self.shutdown.is_stopped() {
+                break;
+            }
+
+            let stream = match stream {
+                Ok(stream) => stream,
+                Err(e) => {
+                    log::warn!("Failed to accept connection: {}", e);
+                    continue;
+                }
+            };
+
+            if !connections.has_room() {
+                log::warn!(
+                    "Connection limit of {} reached, closing new connection",
+                    self.max_connections
+                );
+                continue;
+            }
+
+            match self.spawn_session(stream, context_factory()) {
+                Ok((control, handle)) => connections.push(control, handle),
+                Err(e) => log::error!("Failed to start session: {}", e),
+            }
+        }
+
+        self.shutdown.closed();
+        connections.close_all();
+
+        let _ = fs::remove_file(path);
+        log::info!("MCP Unix socket server `{}` stopped", self.config.name);
+        Ok(())
+    }
+
+    /// Run the setup closure once, on top of anything already registered
+    fn build_registry(&self) -> Result<()> {
+        if let Some(setup) = &self.setup {
+            let mut server = Server::with_registry(self.config.clone(), self.registry.snapshot());
+            setup(&mut server)?;
+            self.registry.replace(server.registry().clone());
+        }
+        Ok(())
+    }
+
+    /// Run one client session on its own thread
+    fn spawn_session(
+        &self,
+        stream: UnixStream,
+        context: C,
+    ) -> Result<(UnixStream, JoinHandle<()>)> {
+        let control = stream.try_clone()?;
+        let reader = BufReader::new(stream.try_clone()?);
+        let transport = StreamTransport::new(reader, stream).with_framing(self.framing);
+
+        let mut server = Server::with_registry(self.config.clone(), self.registry.snapshot());
+        let handle = thread::spawn(move || {
+            if let Err(e) = server.start(transport, context) {
+                log::warn!("Session ended with error: {}", e);
+            }
+        });
+
+        Ok((control, handle))
+    }
+}
+
+/// Bind a socket at `path` that nobody else can reach before it has `mode`
+///
+/// The socket is bound in a fresh directory only the owner can enter, given
+/// its permissions there, then renamed into place.
+fn bind_private(path: &Path, mode: u32) -> Result<UnixListener> {
+    static STAGING: AtomicUsize = AtomicUsize::new(0);
+
+    let parent = match path.parent() {
+        Some(parent) if !parent.as_os_str().is_empty() => parent,
+        _ => Path::new("."),
+    };
+    let n = STAGING.fetch_add(1, Ordering::SeqCst);
+    let staging = parent.join(format!(".mcp-{}-{}", std::process::id(), n));
+    fs::DirBuilder::new().mode(0o700).create(&staging)?;
+
+    let staged = staging.join("s");
+    let bound = UnixListener::bind(&staged).and_then(|listener| {
+        fs::set_permissions(&staged, fs::Permissions::from_mode(mode))?;
+        fs::rename(&staged, path)?;
+        Ok(listener)
+    });
+
+    let _ = fs::remove_file(&staged);
+    let _ = fs::remove_dir(&staging);
+    Ok(bound?)
+}
+
+/// Remove a socket file nobody is listening on
+fn remove_stale_socket(path: &Path) -> Result<()> {
+    let metadata = match fs::symlink_metadata(path) {
+        Ok(metadata) => metadata,
+        Err(e) if e.kind() == io::ErrorKind::NotFound => return Ok(()),
+        Err(e) => return Err(e.into()),
+    };
+
+    if !metadata.file_type().is_socket() {
+        return Err(McpError::Internal(format!(
+            "{} exists and is not a socket",
+            path.display()
+        )));
+    }
+
+    if UnixStream::connect(path).is_ok() {
+        return Err(McpError::Internal(format!(
+            "{} is in use by another server",
+            path.display()
+        )));
+    }
+
+    fs::remove_file(path)?;
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::server::{Tool, ToolEnv};
+    use crate::types::CallToolResult;
+    use serde_json::Value;
+    use std::io::{BufRead, Write};
+    use std::path::PathBuf;
+    use std::sync::Arc;
+    use std::sync::atomic::{AtomicI64, AtomicU16};
+    use std::time::Duration;
+
+    // Socket counter to avoid conflicts between tests
+    static SOCKET: AtomicU16 = AtomicU16::new(0);
+
+    fn socket_path() -> PathBuf {
+        let n = SOCKET.fetch_add(1, Ordering::SeqCst);
+        std::env::temp_dir().join(format!("sml_mcps-{}-{}.sock", std::process::id(), n))
+    }
+
+    struct TestContext {
+        counter: Arc<AtomicI64>,
+    }
+
+    struct CounterTool;
+    impl Tool<TestContext> for CounterTool {
+        fn name(&self) -> &str {
+            "counter"
+        }
+        fn description(&self) -> &str {
+            "Increment shared counter"
+        }
+        fn schema(&self) -> Value {
+            serde_json::json!({ "type": "object", "properties": {} })
+        }
+        fn execute(
+            &self,
+            _args: Value,
+            ctx: &mut TestContext,
+            _env: &ToolEnv,
+        ) -> Result<CallToolResult> {
+            let val = ctx.counter.fetch_add(1, Ordering::SeqCst) + 1;
+            Ok(CallToolResult::text(format!("Counter: {}", val)))
+        }
+    }
+
+    /// Start a server with the counter tool on a fresh socket, returning its
+    /// path and shutdown handle
+    fn spawn_server(counter: Arc<AtomicI64>) -> (PathBuf, ShutdownHandle, JoinHandle<Result<()>>) {
+        let server =
+            UnixServer::new(ServerConfig::default()).with_tools(|s: &mut Server<TestContext>| {
+                s.add_tool(CounterTool)?;
+                Ok(())
+            });
+        spawn(server, counter)
+    }
+
+    fn spawn(
+        server: UnixServer<TestContext>,
+        counter: Arc<AtomicI64>,
+    ) -> (PathBuf, ShutdownHandle, JoinHandle<Result<()>>) {
+        let path = socket_path();
+        let shutdown = server.shutdown_handle();
+
+        let server_path = path.clone();
+        let handle = thread::spawn(move || {
+            server.serve(&server_path, move || TestContext {
+                counter: counter.clone(),
+            })
+        });
+
+        thread::sleep(Duration::from_millis(100));
+        (path, shutdown, handle)
+    }
+
+    /// Simple line-based client
+    struct Client {
+        reader: BufReader<UnixStream>,
+        writer: UnixStream,
+    }
+
+    impl Client {
+        fn connect(path: &Path) -> Self {
+            let stream = UnixStream::connect(path).unwrap();
+            stream
+                .set_read_timeout(Some(Duration::from_secs(5)))
+                .unwrap();
+            Self {
+                reader: BufReader::new(stream.try_clone().unwrap()),
+                writer: stream,
+            }
+        }
+
+        fn send(&mut self, message: &str) {
+            writeln!(self.writer, "{}", message).unwrap();
+        }
+
+        fn call(&mut self, message: &str) -> Value {
+            self.send(message);
+            let mut line = String::new();
+            self.reader.read_line(&mut line).unwrap();
+            serde_json::from_str(&line).unwrap()
+        }
+
+        fn initialize(&mut self) {
+            let response = self.call(r#"{"jsonrpc":"2.0","id":0,"method":"initialize"}"#);
+            assert!(response["result"]["serverInfo"].is_object());
+            self.send(r#"{"jsonrpc":"2.0","method":"notifications/initialized"}"#);
+        }
+    }
+
+    const CALL: &str =
+        r#"{"jsonrpc":"2.0","id":1,"method":"tools/call","params":{"name":"counter"}}"#;
+
+    #[test]
+    fn test_unix_server_concurrent_sessions() {
+        let counter = Arc::new(AtomicI64::new(0));
+        let (path, shutdown, handle) = spawn_server(counter.clone());
+
+        let mut first = Client::connect(&path);
+        let mut second = Client::connect(&path);
+
+        // Each connection has its own lifecycle
+        first.initialize();
+        let response = second.call(CALL);
+        assert_eq!(response["error"]["code"], -32600);
+
+        second.initialize();
+        let response = first.call(CALL);
+        assert_eq!(response["result"]["content"][0]["text"], "Counter: 1");
+        let response = second.call(CALL);
+        assert_eq!(response["result"]["content"][0]["text"], "Counter: 2");
+
+        shutdown.shutdown();
+        assert!(handle.join().unwrap().is_ok());
+        assert_eq!(counter.load(Ordering::SeqCst), 2);
+    }
+
+    #[test]
+    fn test_unix_server_connection_limit() {
+        let server = UnixServer::new(ServerConfig::default()).max_connections(1);
+        let (path, shutdown, handle) = spawn(server, Arc::new(AtomicI64::new(0)));
+
+        let mut first = Client::connect(&path);
+        first.initialize();
+
+        // Over the limit, the connection is closed straight away
+        let mut second = Client::connect(&path);
+        let mut line = String::new();
+        assert_eq!(second.reader.read_line(&mut line).unwrap(), 0);
+
+        // Its slot frees up once the first client leaves
+        drop(first);
+        thread::sleep(Duration::from_millis(100));
+        Client::connect(&path).initialize();
+
+        shutdown.shutdown();
+        handle.join().unwrap().unwrap();
+    }
+
+    #[test]
+    fn test_unix_server_shared_registry() {
+        let setups = Arc::new(AtomicU16::new(0));
+        let counted = setups.clone();
+        let server = UnixServer::new(ServerConfig::default()).with_tools(
+            move |s: &mut Server<TestContext>| {
+                counted.fetch_add(1, Ordering::SeqCst);
+                s.add_tool(CounterTool)
+            },
+        );
+        let registry = server.registry();
+        let (path, shutdown, handle) = spawn(server, Arc::new(AtomicI64::new(0)));
+
+        let list = r#"{"jsonrpc":"2.0","id":1,"method":"tools/list"}"#;
+        let tools = |path: &Path| {
+            let mut client = Client::connect(path);
+            client.initialize();
+            client.call(list)["result"]["tools"]
+                .as_array()
+                .unwrap()
+                .len()
+        };
+        assert_eq!(tools(&path), 1);
+        assert_eq!(tools(&path), 1);
+
+        // Later connections see changes made while serving
+        assert!(registry.update(|r| r.remove_tool("counter")));
+        assert_eq!(tools(&path), 0);
+        assert_eq!(setups.load(Ordering::SeqCst), 1);
+
+        shutdown.shutdown();
+        handle.join().unwrap().unwrap();
+    }
+
+    #[test]
+    fn test_unix_server_socket_permissions() {
+        let (path, shutdown, handle) = spawn_server(Arc::new(AtomicI64::new(0)));
+
+        let mode = fs::metadata(&path).unwrap().permissions().mode();
+        assert_eq!(mode & 0o777, 0o600);
+
+        shutdown.shutdown();
+        handle.join().unwrap().unwrap();
+    }
+
+    #[test]
+    fn test_unix_server_custom_permissions() {
+        let path = socket_path();
+        let server = UnixServer::<TestContext>::new(ServerConfig::default()).permissions(0o660);
+        let shutdown = server.shutdown_handle();
+        let server_path = path.clone();
+        let handle = thread::spawn(move || {
+            server.serve(&server_path, || TestContext {
+                counter: Arc::new(AtomicI64::new(0)),
+            })
+        });
+        thread::sleep(Duration::from_millis(100));
+
+        let mode = fs::metadata(&path).unwrap().permissions().mode();
+        assert_eq!(mode & 0o777, 0o660);
+        Client::connect(&path).initialize();
+
+        shutdown.shutdown();
+        handle.join().unwrap().unwrap();
+    }
+
+    #[test]
+    fn test_unix_server_shutdown_before_serve() {
+        let path = socket_path();
+        let server = UnixServer::<TestContext>::new(ServerConfig::default());
+        server.shutdown_handle().shutdown();
+
+        let started = std::time::Instant::now();
+        server
+            .serve(&path, || TestContext {
+                counter: Arc::new(AtomicI64::new(0)),
+            })
+            .unwrap();
+        assert!(started.elapsed() < Duration::from_secs(1));
+        assert!(!path.exists());
+    }
+
+    #[test]
+    fn test_unix_server_shutdown_closes_sessions_and_socket() {
+        let (path, shutdown, handle) = spawn_server(Arc::new(AtomicI64::new(0)));
+
+        let mut client = Client::connect(&path);
+        client.initialize();
+
+        shutdown.shutdown();
+        handle.join().unwrap().unwrap();
+
+        // Client sees EOF and the socket file is gone
+        let mut line = String::new();
+        assert_eq!(client.reader.read_line(&mut line).unwrap(), 0);
+        assert!(!path.exists());
+    }
+
+    #[test]
+    fn test_remove_stale_socket() {
+        let path = socket_path();
+
+        // Missing path is fine
+        assert!(remove_stale_socket(&path).is_ok());
+
+        // Listener dropped without cleanup leaves a stale file
+        drop(UnixListener::bind(&path).unwrap());
+        assert!(path.exists());
+        remove_stale_socket(&path).unwrap();
+        assert!(!path.exists());
+
+        // A live listener is not touched
+        let _listener = UnixListener::bind(&path).unwrap();
+        assert!(remove_stale_socket(&path).is_err());
+        assert!(path.exists());
+        fs::remove_file(&path).unwrap();
+
+        // Regular files are never removed
+        fs::write(&path, "not a socket").unwrap();
+        assert!(remove_stale_socket(&path).is_err());
+        fs::remove_file(&path).unwrap();
+    }
+}
@@ -0,0 +1,862 @@
+//! WebSocket Transport
+//!
+//! One JSON-RPC message per text frame over a sync WebSocket (tungstenite).
+//! Each connection is a long-lived session with its own server and context.
+
+use crate::server::{Server, ServerConfig};
+use crate::transport::connections::{Connections, DEFAULT_MAX_CONNECTIONS, ShutdownHandle};
+use crate::transport::origin::OriginPolicy;
+use crate::transport::stream::DEFAULT_MAX_MESSAGE_SIZE;
+use crate::transport::{SetupFn, Transport, parse_message};
+use crate::types::{JsonRpcMessage, McpError, Result};
+use std::io::{self, Read, Write};
+use std::net::{IpAddr, Ipv4Addr, Ipv6Addr, SocketAddr, TcpListener, TcpStream};
+use std::sync::Arc;
+use std::thread;
+use std::time::Duration;
+use tungstenite::error::CapacityError;
+use tungstenite::handshake::server::{ErrorResponse, Request, Response};
+use tungstenite::http::StatusCode;
+use tungstenite::protocol::WebSocketConfig;
+use tungstenite::{Error as WsError, Message, WebSocket};
+
+#[cfg(feature = "auth")]
+use crate::auth::{AuthRequest, Authenticator, Claims};
+
+/// How long a client may take to complete the opening handshake
+const HANDSHAKE_TIMEOUT: Duration = Duration::from_secs(10);
+
+/// WebSocket transport - one JSON-RPC message per text frame
+///
+/// Pings are answered automatically while reading. Binary frames are
+/// rejected as invalid messages.
+pub struct WebSocketTransport<S: Read + Write = TcpStream> {
+    socket: WebSocket<S>,
+}
+
+impl<S: Read + Write> WebSocketTransport<S> {
+    /// Wrap an already-established WebSocket
+    pub fn new(socket: WebSocket<S>) -> Self {
+        Self { socket }
+    }
+
+    /// Get the underlying WebSocket
+    pub fn into_inner(self) -> WebSocket<S> {
+        self.socket
+    }
+}
+
+impl<S: Read + Write + Send + Sync> Transport for WebSocketTransport<S> {
+    fn read(&mut self) -> Result<JsonRpcMessage> {
+        loop {
+            match self.socket.read().map_err(map_ws_error)? {
+                Message::Text(text) => return parse_message(&text),
+                Message::Binary(_) => {
+                    return Err(McpError::InvalidMessage(
+                        "Binary frames are not supported".into(),
+                    ));
+                }
+                // Pongs are queued by tungstenite; flush so idle peers see them
+                Message::Ping(_) => self.socket.flush().map_err(map_ws_error)?,
+                // The close reply is queued; the next read reports the closure
+                Message::Pong(_) | Message::Close(_) | Message::Frame(_) => {}
+            }
+        }
+    }
+
+    fn write(&mut self, message: &JsonRpcMessage) -> Result<()> {
+        let json = serde_json::to_string(message)?;
+        self.socket.send(Message::Text(json)).map_err(map_ws_error)
+    }
+
+    fn close(&mut self) -> Result<()> {
+        match self.socket.close(None) {
+            Ok(()) => self.socket.flush().or_else(ignore_closed),
+            Err(e) => ignore_closed(e),
+        }
+    }
+}
+
+/// Map tungstenite errors onto the crate's error type
+fn map_ws_error(e: WsError) -> McpError {
+    match e {
+        WsError::ConnectionClosed | WsError::AlreadyClosed => McpError::TransportClosed,
+        WsError::Io(e)
+            if matches!(
+                e.kind(),
+                io::ErrorKind::UnexpectedEof
+                    | io::ErrorKind::ConnectionReset
+                    | io::ErrorKind::BrokenPipe
+            ) =>
+        {
+            McpError::TransportClosed
+        }
+        WsError::Io(e) => McpError::Io(e),
+        WsError::Capacity(CapacityError::MessageTooLong { max_size, .. }) => {
+            McpError::MessageTooLarge(max_size)
+        }
+        WsError::Utf8 => McpError::InvalidMessage("Text frame is not valid UTF-8".into()),
+        other => McpError::Internal(format!("WebSocket error: {}", other)),
+    }
+}
+
+/// Where to connect to reach a listener bound to `addr`
+fn wake_addr(addr: SocketAddr) -> SocketAddr {
+    match addr.ip() {
+        IpAddr::V4(ip) if ip.is_unspecified() => (Ipv4Addr::LOCALHOST, addr.port()).into(),
+        IpAddr::V6(ip) if ip.is_unspecified() => (Ipv6Addr::LOCALHOST, addr.port()).into(),
+        _ => addr,
+    }
+}
+
+/// Closing an already-closed socket is not an error
+fn ignore_closed(e: WsError) -> Result<()> {
+    match map_ws_error(e) {
+        McpError::TransportClosed => Ok(()),
+        e => Err(e),
+    }
+}
+
+//
+// WebSocketServer - high-level server wrapper
+//
+
+/// High-level WebSocket MCP server
+///
+/// Each accepted connection, up to `max_connections`, is handed to its own
+/// thread, which performs the opening handshake (origin check and
+/// authentication included), then runs a fresh `Server` (configured by
+/// `with_tools`) and context until the client disconnects or the server is
+/// shut down.
+///
+/// # Example
+/// ```ignore
+/// WebSocketServer::new(config)
+///     .with_tools(|s| {
+///         s.add_tool(EchoTool)?;
+///         Ok(())
+///     })
+///     .serve("127.0.0.1:3000", || AppContext::new())?;
+/// ```
+pub struct WebSocketServer<C> {
+    config: ServerConfig,
+    endpoint: String,
+    setup: Option<SetupFn<C>>,
+    max_message_size: usize,
+    max_connections: usize,
+    origins: OriginPolicy,
+    shutdown: ShutdownHandle,
+}
+
+impl<C: Send + Sync + 'static> WebSocketServer<C> {
+    /// Create a new WebSocket server with the given configuration
+    pub fn new(config: ServerConfig) -> Self {
+        Self {
+            config,
+            endpoint: "/mcp".to_string(),
+            setup: None,
+            max_message_size: DEFAULT_MAX_MESSAGE_SIZE,
+            max_connections: DEFAULT_MAX_CONNECTIONS,
+            origins: OriginPolicy::default(),
+            shutdown: ShutdownHandle::default(),
+        }
+    }
+
+    /// Set the endpoint path (default: "/mcp")
+    pub fn endpoint(mut self, path: impl Into<String>) -> Self {
+        self.endpoint = path.into();
+        self
+    }
+
+    /// Set the maximum accepted message size in bytes
+    pub fn with_max_message_size(mut self, max: usize) -> Self {
+        self.max_message_size = max;
+        self
+    }
+
+    /// Most connections served at once (default: 256)
+    ///
+    /// Connections beyond this are closed as soon as they are accepted.
+    pub fn max_connections(mut self, max: usize) -> Self {
+        self.max_connections = max;
+        self
+    }
+
+    /// Get a handle to stop the server, before or while it serves
+    pub fn shutdown_handle(&self) -> ShutdownHandle {
+        self.shutdown.clone()
+    }
+
+    /// Only accept handshakes from these browser origins (403 otherwise)
+    ///
+    /// Same rules and defaults as `HttpServer::allowed_origins`: localhost
+    /// origins when bound to a loopback address, any origin otherwise.
+    pub fn allowed_origins<I, S>(mut self, origins: I) -> Self
+    where
+        I: IntoIterator<Item = S>,
+        S: Into<String>,
+    {
+        self.origins
+            .set_origins(origins.into_iter().map(Into::into).collect());
+        self
+    }
+
+    /// Only accept handshakes whose `Host` header is in this list (403 otherwise)
+    ///
+    /// Same rules and defaults as `HttpServer::allowed_hosts`.
+    pub fn allowed_hosts<I, S>(mut self, hosts: I) -> Self
+    where
+        I: IntoIterator<Item = S>,
+        S: Into<String>,
+    {
+        self.origins
+            .set_hosts(hosts.into_iter().map(Into::into).collect());
+        self
+    }
+
+    /// Configure tools via a setup closure
+    ///
+    /// The closure is called once per connection to set up its server.
+    pub fn with_tools<F>(mut self, setup: F) -> Self
+    where
+        F: Fn(&mut Server<C>) -> Result<()> + Send + Sync + 'static,
+    {
+        self.setup = Some(Box::new(setup));
+        self
+    }
+
+    /// Serve without authentication
+    ///
+    /// The context factory is called for each connection, on that
+    /// connection's thread.
+    pub fn serve<F>(mut self, addr: &str, context_factory: F) -> Result<()>
+    where
+        F: Fn() -> C + Send + Sync + 'static,
+    {
+        let listener = TcpListener::bind(addr)?;
+        self.origins = self.origins.for_addr(addr);
+
+        log::info!(
+            "MCP WebSocket server `{}` listening on ws://{}{}",
+            self.config.name,
+            addr,
+            self.endpoint
+        );
+
+        let server = Arc::new(self);
+        let context_factory = Arc::new(context_factory);
+        server.clone().accept(listener, move |stream| {
+            let socket = match server.handshake(stream, |_| Ok(())) {
+                Ok((socket, ())) => socket,
+                Err(e) => {
+                    log::info!("Handshake failed: {}", e);
+                    return;
+                }
+            };
+
+            #[cfg(feature = "auth")]
+            server.run_session(socket, context_factory(), None);
+            #[cfg(not(feature = "auth"))]
+            server.run_session(socket, context_factory());
+        })
+    }
+
+    /// Serve with authentication
+    ///
+    /// `authenticator` checks the opening handshake (its headers and the
+    /// peer address; there is no body) and the context factory receives
+    /// the claims for the connection. Both run on the connection's thread.
+    #[cfg(feature = "auth")]
+    pub fn serve_with_auth<A, F>(
+        mut self,
+        addr: &str,
+        authenticator: A,
+        context_factory: F,
+    ) -> Result<()>
+    where
+        A: Authenticator + 'static,
+        F: Fn(&Claims) -> C + Send + Sync + 'static,
+    {
+        let listener = TcpListener::bind(addr)?;
+        self.origins = self.origins.for_addr(addr);
+
+        log::info!(
+            "MCP WebSocket server `{}` (authenticated) listening on ws://{}{}",
+            self.config.name,
+            addr,
+            self.endpoint
+        );
+
+        let server = Arc::new(self);
+        server.clone().accept(listener, move |stream| {
+            let peer = stream.peer_addr().ok();
+            let handshake = server.handshake(stream, |request| {
+                let headers: Vec<(String, String)> = request
+                    .headers()
+                    .iter()
+                    .filter_map(|(name, value)| {
+                        Some((name.to_string(), value.to_str().ok()?.to_string()))
+                    })
+                    .collect();
+                let url = request.uri().path_and_query().map_or("/", |pq| pq.as_str());
+                let auth_request = AuthRequest {
+                    method: "GET",
+                    url,
+                    headers: &headers,
+                    body: &[],
+                    peer,
+                };
+                authenticator
+                    .authenticate(&auth_request)
+                    .map_err(|e| e.to_string())?
+                    .ok_or("Missing credentials".to_string())
+            });
+
+            let (socket, claims) = match handshake {
+                Ok(accepted) => accepted,
+                Err(e) => {
+                    log::info!("Handshake failed: {}", e);
+                    return;
+                }
+            };
+
+            log::debug!("Authenticated");
+
+            let context = context_factory(&claims);
+            server.run_session(socket, context, Some(claims));
+        })
+    }
+
+    /// Accept connections until shut down, each running `connection` on its own thread
+    fn accept<F>(self: Arc<Self>, listener: TcpListener, connection: F) -> Result<()>
+    where
+        F: Fn(TcpStream) + Send + Sync + 'static,
+    {
+        let wake_addr = wake_addr(listener.local_addr()?);
+        // Wake the blocking accept() with a throwaway connection
+        let listening = self.shutdown.listening(move || {
+            let _ = TcpStream::connect_timeout(&wake_addr, Duration::from_secs(1));
+        });
+        if !listening {
+            return Ok(());
+        }
+
+        let connection = Arc::new(connection);
+        let mut connections = Connections::new(self.max_connections);
+        for stream in listener.incoming() {
+            if self.shutdown.is_stopped() {
+                break;
+            }
+
+            let stream = match stream {
+                Ok(stream) => stream,
+                Err(e) => {
+                    log::warn!("Failed to accept connection: {}", e);
+                    continue;
+                }
+            };
+
+            if !connections.has_room() {
+                log::warn!(
+                    "Connection limit of {} reached, closing new connection",
+                    self.max_connections
+                );
+                continue;
+            }
+
+            let control = match stream.try_clone() {
+                Ok(control) => control,
+                Err(e) => {
+                    log::error!("Failed to start session: {}", e);
+                    continue;
+                }
+            };
+            let connection = connection.clone();
+            let handle = thread::spawn(move || connection(stream));
+            connections.push(control, handle);
+        }
+
+        self.shutdown.closed();
+        connections.close_all();
+        log::info!("MCP WebSocket server `{}` stopped", self.config.name);
+        Ok(())
+    }
+
+    /// Perform the opening handshake, checking the endpoint path and origin
+    ///
+    /// `authorize` may reject the upgrade with a reason, answered as 401.
+    /// What it returns for an accepted upgrade is returned with the socket.
+    fn handshake<T>(
+        &self,
+        stream: TcpStream,
+        authorize: impl FnOnce(&Request) -> std::result::Result<T, String>,
+    ) -> Result<(WebSocket<TcpStream>, T)> {
+        // Don't let a stalled client hold its thread forever
+        stream.set_read_timeout(Some(HANDSHAKE_TIMEOUT))?;
+
+        let config = WebSocketConfig {
+            max_message_size: Some(self.max_message_size),
+            max_frame_size: Some(self.max_message_size),
+            ..Default::default()
+        };
+
+        let mut authorized = None;
+        // ErrorResponse is tungstenite's type, so its size isn't ours to pick
+        #[allow(clippy::result_large_err)]
+        let callback = |request: &Request, response: Response| {
+            log::debug!("GET {}", request.uri());
+
+            if request.uri().path() != self.endpoint {
+                return Err(error_response(StatusCode::NOT_FOUND, "Not Found".into()));
+            }
+            let header = |name| request.headers().get(name)?.to_str().ok();
+            if let Err(reason) = self.origins.check(header("Origin"), header("Host")) {
+                log::info!("Rejected handshake: {}", reason);
+                return Err(error_response(StatusCode::FORBIDDEN, reason));
+            }
+            let authorization = authorize(request).map_err(|e| {
+                error_response(StatusCode::UNAUTHORIZED, format!("Unauthorized: {}", e))
+            })?;
+            authorized = Some(authorization);
+            Ok(response)
+        };
+
+        let socket = tungstenite::accept_hdr_with_config(stream, callback, Some(config))
+            .map_err(|e| McpError::Internal(e.to_string()))?;
+        let authorized = authorized
+            .ok_or_else(|| McpError::Internal("Handshake completed without a request".into()))?;
+        socket.get_ref().set_read_timeout(None)?;
+        Ok((socket, authorized))
+    }
+
+    /// Run one client session until it disconnects
+    fn run_session(
+        &self,
+        socket: WebSocket<TcpStream>,
+        context: C,
+        #[cfg(feature = "auth")] claims: Option<Claims>,
+    ) {
+        let mut server: Server<C> = Server::new(self.config.clone());
+        #[cfg(feature = "auth")]
+        server.set_claims(claims);
+        if let Some(ref setup) = self.setup {
+            if let Err(e) = setup(&mut server) {
+                log::error!("Failed to start session: {}", e);
+                return;
+            }
+        }
+
+        if let Err(e) = server.start(WebSocketTransport::new(socket), context) {
+            log::warn!("Session ended with error: {}", e);
+        }
+    }
+}
+
+/// Build a plain-text handshake rejection
+fn error_response(status: StatusCode, body: String) -> ErrorResponse {
+    let mut response = ErrorResponse::new(Some(body));
+    *response.status_mut() = status;
+    response
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::server::{Tool, ToolEnv};
+    use crate::types::CallToolResult;
+    use serde_json::Value;
+    use std::sync::atomic::{AtomicU16, Ordering};
+    use tungstenite::client::IntoClientRequest;
+
+    // Port counter to avoid conflicts between tests
+    static PORT: AtomicU16 = AtomicU16::new(14000);
+
+    fn next_addr() -> String {
+        format!("127.0.0.1:{}", PORT.fetch_add(1, Ordering::SeqCst))
+    }
+
+    struct TestContext;
+
+    struct EchoTool;
+    impl Tool<TestContext> for EchoTool {
+        fn name(&self) -> &str {
+            "echo"
+        }
+        fn description(&self) -> &str {
+            "Echo back the message"
+        }
+        fn schema(&self) -> Value {
+            serde_json::json!({ "type": "object", "properties": { "message": { "type": "string" } } })
+        }
+        fn execute(
+            &self,
+            args: Value,
+            _ctx: &mut TestContext,
+            _env: &ToolEnv,
+        ) -> Result<CallToolResult> {
+            let message = args.get("message").and_then(|m| m.as_str()).unwrap_or("");
+            Ok(CallToolResult::text(format!("Echo: {}", message)))
+        }
+    }
+
+    fn spawn_server() -> String {
+        let addr = next_addr();
+        let server = WebSocketServer::new(ServerConfig::default())
+            .with_max_message_size(1024)
+            .with_tools(|s: &mut Server<TestContext>| {
+                s.add_tool(EchoTool)?;
+                Ok(())
+            });
+
+        let server_addr = addr.clone();
+        thread::spawn(move || server.serve(&server_addr, || TestContext));
+        thread::sleep(Duration::from_millis(100));
+        addr
+    }
+
+    type Client = WebSocket<TcpStream>;
+
+    /// Open a client connection, returning the handshake status on rejection
+    fn try_connect(addr: &str, path: &str) -> std::result::Result<Client, StatusCode> {
+        let stream = TcpStream::connect(addr).unwrap();
+        stream
+            .set_read_timeout(Some(Duration::from_secs(5)))
+            .unwrap();
+        match tungstenite::client(format!("ws://{}{}", addr, path), stream) {
+            Ok((socket, _)) => Ok(socket),
+            Err(tungstenite::HandshakeError::Failure(WsError::Http(response))) => {
+                Err(response.status())
+            }
+            Err(e) => panic!("Handshake failed: {}", e),
+        }
+    }
+
+    fn connect(addr: &str) -> Client {
+        try_connect(addr, "/mcp").unwrap()
+    }
+
+    fn call(client: &mut Client, message: &str) -> Value {
+        client.send(Message::Text(message.to_string())).unwrap();
+        loop {
+            match client.read().unwrap() {
+                Message::Text(text) => return serde_json::from_str(&text).unwrap(),
+                Message::Pong(_) => continue,
+                other => panic!("Unexpected frame: {:?}", other),
+            }
+        }
+    }
+
+    fn initialize(client: &mut Client) {
+        let response = call(client, r#"{"jsonrpc":"2.0","id":0,"method":"initialize"}"#);
+        assert!(response["result"]["serverInfo"].is_object());
+        client
+            .send(Message::Text(
+                r#"{"jsonrpc":"2.0","method":"notifications/initialized"}"#.into(),
+            ))
+            .unwrap();
+    }
+
+    #[test]
+    fn test_websocket_session() {
+        let addr = spawn_server();
+        let mut client = connect(&addr);
+
+        initialize(&mut client);
+        let response = call(
+            &mut client,
+            r#"{"jsonrpc":"2.0","id":1,"method":"tools/call","params":{"name":"echo","arguments":{"message":"hi"}}}"#,
+        );
+        assert_eq!(response["id"], 1);
+        assert_eq!(response["result"]["content"][0]["text"], "Echo: hi");
+
+        client.close(None).unwrap();
+    }
+
+    #[test]
+    fn test_websocket_connections_are_independent_sessions() {
+        let addr = spawn_server();
+        let mut first = connect(&addr);
+        let mut second = connect(&addr);
+
+        initialize(&mut first);
+        let response = call(
+            &mut second,
+            r#"{"jsonrpc":"2.0","id":1,"method":"tools/list"}"#,
+        );
+        assert_eq!(response["error"]["code"], -32600);
+
+        let response = call(
+            &mut first,
+            r#"{"jsonrpc":"2.0","id":1,"method":"tools/list"}"#,
+        );
+        assert!(response["result"]["tools"].is_array());
+    }
+
+    #[test]
+    fn test_websocket_answers_ping() {
+        let addr = spawn_server();
+        let mut client = connect(&addr);
+
+        client.send(Message::Ping(b"hello".to_vec())).unwrap();
+        match client.read().unwrap() {
+            Message::Pong(payload) => assert_eq!(payload, b"hello"),
+            other => panic!("Expected pong, got {:?}", other),
+        }
+    }
+
+    #[test]
+    fn test_websocket_rejects_bad_frames() {
+        let addr = spawn_server();
+        let mut client = connect(&addr);
+
+        let response = call(&mut client, "{not json");
+        assert_eq!(response["error"]["code"], -32700);
+        assert_eq!(response["id"], Value::Null);
+
+        client.send(Message::Binary(b"{}".to_vec())).unwrap();
+        match client.read().unwrap() {
+            Message::Text(text) => {
+                let response: Value = serde_json::from_str(&text).unwrap();
+                assert_eq!(response["error"]["code"], -32600);
+            }
+            other => panic!("Unexpected frame: {:?}", other),
+        }
+
+        // Session survives bad input
+        initialize(&mut client);
+    }
+
+    #[test]
+    fn test_websocket_wrong_path_rejected() {
+        let addr = spawn_server();
+        assert_eq!(
+            try_connect(&addr, "/other").err(),
+            Some(StatusCode::NOT_FOUND)
+        );
+    }
+
+    #[test]
+    fn test_websocket_stalled_handshake_does_not_block() {
+        let addr = spawn_server();
+
+        // Connects but never sends its handshake
+        let _stalled = TcpStream::connect(&addr).unwrap();
+        thread::sleep(Duration::from_millis(50));
+
+        let mut client = connect(&addr);
+        initialize(&mut client);
+    }
+
+    #[test]
+    fn test_websocket_origin_checked() {
+        let addr = spawn_server();
+        let connect_from = |origin: &str| {
+            let mut request = format!("ws://{}/mcp", addr).into_client_request().unwrap();
+            request
+                .headers_mut()
+                .insert("Origin", origin.parse().unwrap());
+            let stream = TcpStream::connect(&addr).unwrap();
+            match tungstenite::client(request, stream) {
+                Ok(_) => Ok(()),
+                Err(tungstenite::HandshakeError::Failure(WsError::Http(response))) => {
+                    Err(response.status())
+                }
+                Err(e) => panic!("Handshake failed: {}", e),
+            }
+        };
+
+        // Bound to loopback, so only localhost pages may connect
+        assert_eq!(connect_from("http://localhost:5173"), Ok(()));
+        assert_eq!(
+            connect_from("https://evil.example"),
+            Err(StatusCode::FORBIDDEN)
+        );
+    }
+
+    #[test]
+    fn test_websocket_shutdown() {
+        let addr = next_addr();
+        let server = WebSocketServer::new(ServerConfig::default());
+        let shutdown = server.shutdown_handle();
+        let server_addr = addr.clone();
+        let handle = thread::spawn(move || server.serve(&server_addr, || TestContext));
+        thread::sleep(Duration::from_millis(100));
+
+        let mut client = connect(&addr);
+        initialize(&mut client);
+
+        // Open sessions are closed and serve returns
+        shutdown.shutdown();
+        handle.join().unwrap().unwrap();
+        assert!(client.read().is_err());
+        assert!(TcpStream::connect(&addr).is_err());
+
+        // A shutdown before serving is remembered
+        let server = WebSocketServer::<TestContext>::new(ServerConfig::default());
+        server.shutdown_handle().shutdown();
+        server.serve(&next_addr(), || TestContext).unwrap();
+    }
+
+    #[test]
+    fn test_websocket_connection_limit() {
+        let addr = next_addr();
+        let server = WebSocketServer::new(ServerConfig::default()).max_connections(1);
+        let shutdown = server.shutdown_handle();
+        let server_addr = addr.clone();
+        let handle = thread::spawn(move || server.serve(&server_addr, || TestContext));
+        thread::sleep(Duration::from_millis(100));
+
+        let mut first = connect(&addr);
+        initialize(&mut first);
+
+        // Over the limit, the connection is closed before any handshake
+        let mut second = TcpStream::connect(&addr).unwrap();
+        second
+            .set_read_timeout(Some(Duration::from_secs(5)))
+            .unwrap();
+        assert!(matches!(second.read(&mut [0; 1]), Ok(0) | Err(_)));
+
+        // Its slot frees up once the first client leaves
+        first.close(None).unwrap();
+        while first.read().is_ok() {}
+        drop(first);
+        thread::sleep(Duration::from_millis(100));
+        initialize(&mut connect(&addr));
+
+        shutdown.shutdown();
+        handle.join().unwrap().unwrap();
+    }
+
+    #[test]
+    fn test_map_ws_error() {
+        assert!(matches!(
+            map_ws_error(WsError::ConnectionClosed),
+            McpError::TransportClosed
+        ));
+        assert!(matches!(
+            map_ws_error(WsError::Io(io::ErrorKind::ConnectionReset.into())),
+            McpError::TransportClosed
+        ));
+        assert!(matches!(
+            map_ws_error(WsError::Capacity(CapacityError::MessageTooLong {
+                size: 2048,
+                max_size: 1024
+            })),
+            McpError::MessageTooLarge(1024)
+        ));
+    }
+
+    #[cfg(feature = "auth")]
+    mod auth_tests {
+        use super::*;
+        use crate::auth::JwtValidator;
+        use jsonwebtoken::{Algorithm, EncodingKey, Header as JwtHeader, encode};
+
+        const SECRET: &[u8] = b"test-secret-key";
+
+        struct AuthContext {
+            user_id: String,
+        }
+
+        struct WhoamiTool;
+        impl Tool<AuthContext> for WhoamiTool {
+            fn name(&self) -> &str {
+                "whoami"
+            }
+            fn description(&self) -> &str {
+                "Return user info"
+            }
+            fn schema(&self) -> Value {
+                serde_json::json!({ "type": "object", "properties": {} })
+            }
+            fn execute(
+                &self,
+                _args: Value,
+                ctx: &mut AuthContext,
+                _env: &ToolEnv,
+            ) -> Result<CallToolResult> {
+                Ok(CallToolResult::text(format!("User: {}", ctx.user_id)))
+            }
+        }
+
+        fn make_token(user_id: &str) -> String {
+            let exp = std::time::SystemTime::now()
+                .duration_since(std::time::UNIX_EPOCH)
+                .unwrap()
+                .as_secs()
+                + 3600;
+            let claims = serde_json::json!({ "sub": user_id, "tenant_id": "acme", "exp": exp });
+            encode(
+                &JwtHeader::new(Algorithm::HS256),
+                &claims,
+                &EncodingKey::from_secret(SECRET),
+            )
+            .unwrap()
+        }
+
+        fn spawn_auth_server() -> String {
+            let addr = next_addr();
+            let server_addr = addr.clone();
+            thread::spawn(move || {
+                WebSocketServer::new(ServerConfig::default())
+                    .with_tools(|s: &mut Server<AuthContext>| {
+                        s.add_tool(WhoamiTool)?;
+                        Ok(())
+                    })
+                    .serve_with_auth(&server_addr, JwtValidator::hs256(SECRET), |claims| {
+                        AuthContext {
+                            user_id: claims.user_id().to_string(),
+                        }
+                    })
+            });
+            thread::sleep(Duration::from_millis(100));
+            addr
+        }
+
+        fn connect_with_token(
+            addr: &str,
+            token: Option<&str>,
+        ) -> std::result::Result<Client, StatusCode> {
+            let mut request = format!("ws://{}/mcp", addr).into_client_request().unwrap();
+            if let Some(token) = token {
+                let bearer = format!("Bearer {}", token).parse().unwrap();
+                request.headers_mut().insert("Authorization", bearer);
+            }
+            let stream = TcpStream::connect(addr).unwrap();
+            match tungstenite::client(request, stream) {
+                Ok((socket, _)) => Ok(socket),
+                Err(tungstenite::HandshakeError::Failure(WsError::Http(response))) => {
+                    Err(response.status())
+                }
+                Err(e) => panic!("Handshake failed: {}", e),
+            }
+        }
+
+        #[test]
+        fn test_websocket_auth_rejects_handshake() {
+            let addr = spawn_auth_server();
+
+            assert_eq!(
+                connect_with_token(&addr, None).err(),
+                Some(StatusCode::UNAUTHORIZED)
+            );
+            assert_eq!(
+                connect_with_token(&addr, Some("not-a-jwt")).err(),
+                Some(StatusCode::UNAUTHORIZED)
+            );
+        }
+
+        #[test]
+        fn test_websocket_auth_context_from_claims() {
+            let addr = spawn_auth_server();
+            let mut client = connect_with_token(&addr, Some(&make_token("alice"))).unwrap();
+
+            initialize(&mut client);
+            let response = call(
+                &mut client,
+                r#"{"jsonrpc":"2.0","id":1,"method":"tools/call","params":{"name":"whoami"}}"#,
+            );
+            assert_eq!(response["result"]["content"][0]["text"], "User: alice");
+        }
+    }
+}
@@ -0,0 +1,95 @@
+//! Body Workers
+//!
+//! A fixed set of threads for the blocking parts of a request that must not
+//! stall `HttpServer`'s serve loop: reading bodies under a read timeout and
+//! draining the bodies of refused requests.
+
+use std::sync::mpsc::{self, Receiver, Sender};
+use std::sync::{Arc, Mutex};
+use std::thread;
+
+/// Threads per server; a client that stops sending holds one until it disconnects
+const WORKERS: usize = 4;
+
+/// Name given to worker threads
+pub(crate) const THREAD_NAME: &str = "mcp-http-body";
+
+type Job = Box<dyn FnOnce() + Send>;
+
+/// Runs jobs on a fixed number of threads, started on first use
+///
+/// However many clients stall, the thread count stays at `WORKERS`; work
+/// beyond that queues until a worker frees up. Threads exit once the pool
+/// is dropped and their current job ends.
+#[derive(Default)]
+pub(crate) struct Workers {
+    jobs: Mutex<Option<Sender<Job>>>,
+}
+
+impl Workers {
+    /// Run `job` on a worker
+    pub(crate) fn run(&self, job: impl FnOnce() + Send + 'static) {
+        let mut jobs = self.jobs.lock().unwrap_or_else(|e| e.into_inner());
+        let sender = jobs.get_or_insert_with(start);
+        if sender.send(Box::new(job)).is_err() {
+            log::error!("Body workers stopped; dropping job");
+        }
+    }
+}
+
+fn start() -> Sender<Job> {
+    let (tx, rx) = mpsc::channel::<Job>();
+    let rx = Arc::new(Mutex::new(rx));
+    for _ in 0..WORKERS {
+        let rx = rx.clone();
+        let spawned = thread::Builder::new()
+            .name(THREAD_NAME.into())
+            .spawn(move || work(&rx));
+        if let Err(e) = spawned {
+            log::error!("Failed to start body worker: {}", e);
+        }
+    }
+    tx
+}
+
+fn work(jobs: &Mutex<Receiver<Job>>) {
+    loop {
+        let job = jobs.lock().unwrap_or_else(|e| e.into_inner()).recv();
+        match job {
+            Ok(job) => job(),
+            Err(_) => return,
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::sync::Barrier;
+    use std::time::Duration;
+
+    #[test]
+    fn test_workers_are_bounded() {
+        let workers = Workers::default();
+        let release = Arc::new(Barrier::new(WORKERS + 1));
+        let (done_tx, done_rx) = mpsc::channel();
+
+        // Stall every worker, then queue more work behind them
+        for _ in 0..WORKERS {
+            let release = release.clone();
+            workers.run(move || {
+                release.wait();
+            });
+        }
+        for i in 0..10 {
+            let done = done_tx.clone();
+            workers.run(move || done.send(i).unwrap());
+        }
+        assert!(done_rx.recv_timeout(Duration::from_millis(100)).is_err());
+
+        release.wait();
+        let mut done: Vec<_> = (0..10).map(|_| done_rx.recv().unwrap()).collect();
+        done.sort();
+        assert_eq!(done, (0..10).collect::<Vec<_>>());
+    }
+}
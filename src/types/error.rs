@@ -17,6 +17,9 @@ pub enum McpError {
     #[error("Invalid message: {0}")]
     InvalidMessage(String),
 
+    #[error("Message exceeds {0} bytes")]
+    MessageTooLarge(usize),
+
     #[error("Method not found: {0}")]
     MethodNotFound(String),
 
@@ -35,9 +38,20 @@ pub enum McpError {
     #[error("Prompt not found: {0}")]
     PromptNotFound(String),
 
+    #[error("Server not initialized")]
+    NotInitialized,
+
+    #[error("Server already initialized")]
+    AlreadyInitialized,
+
     #[cfg(feature = "auth")]
     #[error("Auth error: {0}")]
     Auth(String),
+
+    /// The caller's token lacks these space-separated scopes
+    #[cfg(feature = "auth")]
+    #[error("Insufficient scope: requires {0}")]
+    InsufficientScope(String),
 }
 
 impl McpError {
@@ -45,6 +59,9 @@ impl McpError {
         match self {
             McpError::Json(e) => JsonRpcError::parse_error(e.to_string()),
             McpError::InvalidMessage(msg) => JsonRpcError::invalid_request(msg),
+            McpError::MessageTooLarge(limit) => {
+                JsonRpcError::invalid_request(format!("Message exceeds {} bytes", limit))
+            }
             McpError::MethodNotFound(method) => {
                 JsonRpcError::method_not_found(format!("Method not found: {}", method))
             }
@@ -57,10 +74,22 @@ impl McpError {
             McpError::PromptNotFound(name) => {
                 JsonRpcError::new(-32002, format!("Prompt not found: {}", name))
             }
+            McpError::NotInitialized => JsonRpcError::invalid_request("Server not initialized"),
+            McpError::AlreadyInitialized => {
+                JsonRpcError::invalid_request("Server already initialized")
+            }
             McpError::Io(e) => JsonRpcError::internal_error(e.to_string()),
             McpError::TransportClosed => JsonRpcError::internal_error("Transport closed"),
             #[cfg(feature = "auth")]
             McpError::Auth(msg) => JsonRpcError::new(-32003, format!("Auth error: {}", msg)),
+            #[cfg(feature = "auth")]
+            McpError::InsufficientScope(scope) => {
+                JsonRpcError::new(-32003, format!("Insufficient scope: requires {}", scope))
+                    .with_data(serde_json::json!({
+                        "error": "insufficient_scope",
+                        "scope": scope,
+                    }))
+            }
         }
     }
 }
@@ -104,6 +133,14 @@ mod tests {
         assert_eq!(rpc_err.code, -32600); // invalid request
     }
 
+    #[test]
+    fn test_message_too_large() {
+        let err = McpError::MessageTooLarge(1024);
+        assert_eq!(err.to_string(), "Message exceeds 1024 bytes");
+        let rpc_err = err.to_jsonrpc_error();
+        assert_eq!(rpc_err.code, -32600); // invalid request
+    }
+
     #[test]
     fn test_method_not_found() {
         let err = McpError::MethodNotFound("unknown/method".into());
@@ -152,6 +189,17 @@ mod tests {
         assert_eq!(rpc_err.code, -32002); // custom error
     }
 
+    #[test]
+    fn test_lifecycle_errors() {
+        let err = McpError::NotInitialized;
+        assert_eq!(err.to_string(), "Server not initialized");
+        assert_eq!(err.to_jsonrpc_error().code, -32600); // invalid request
+
+        let err = McpError::AlreadyInitialized;
+        assert_eq!(err.to_string(), "Server already initialized");
+        assert_eq!(err.to_jsonrpc_error().code, -32600); // invalid request
+    }
+
     #[cfg(feature = "auth")]
     #[test]
     fn test_auth_error() {
@@ -159,6 +207,12 @@ mod tests {
         assert!(err.to_string().contains("invalid token"));
         let rpc_err = err.to_jsonrpc_error();
         assert_eq!(rpc_err.code, -32003); // custom error
+
+        let err = McpError::InsufficientScope("admin".into());
+        assert_eq!(err.to_string(), "Insufficient scope: requires admin");
+        let rpc_err = err.to_jsonrpc_error();
+        assert_eq!(rpc_err.code, -32003);
+        assert_eq!(rpc_err.data.unwrap()["error"], "insufficient_scope");
     }
 
     #[test]
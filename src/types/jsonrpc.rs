@@ -15,11 +15,14 @@ impl Default for JsonRpcVersion {
 }
 
 /// Request ID - can be number or string per JSON-RPC spec
+///
+/// `Null` is only used for errors answering a message whose id couldn't be read.
 #[derive(Debug, Clone, Serialize, Deserialize, PartialEq, Eq, Hash)]
 #[serde(untagged)]
 pub enum RequestId {
     Number(i64),
     String(String),
+    Null,
 }
 
 impl From<i64> for RequestId {
@@ -39,6 +42,7 @@ impl std::fmt::Display for RequestId {
         match self {
             RequestId::Number(n) => write!(f, "{}", n),
             RequestId::String(s) => write!(f, "{}", s),
+            RequestId::Null => write!(f, "null"),
         }
     }
 }
@@ -219,4 +223,14 @@ mod tests {
         let json2 = serde_json::to_string(&req2).unwrap();
         assert!(json2.contains("\"id\":\"abc-123\""));
     }
+
+    #[test]
+    fn test_null_id_error() {
+        let err = JsonRpcMessage::error(RequestId::Null, JsonRpcError::parse_error("bad json"));
+        let json = serde_json::to_string(&err).unwrap();
+        assert!(json.contains("\"id\":null"));
+
+        let parsed: JsonRpcMessage = serde_json::from_str(&json).unwrap();
+        assert_eq!(parsed, err);
+    }
 }
@@ -9,6 +9,20 @@ use std::collections::HashMap;
 /// Current MCP protocol version
 pub const PROTOCOL_VERSION: &str = "2025-03-26";
 
+/// Protocol versions this server can speak, newest first
+pub const SUPPORTED_PROTOCOL_VERSIONS: &[&str] = &[PROTOCOL_VERSION, "2024-11-05"];
+
+/// Pick the protocol version to answer an `initialize` with
+///
+/// Echoes the client's version when supported, otherwise offers the latest.
+pub fn negotiate_protocol_version(requested: &str) -> &'static str {
+    SUPPORTED_PROTOCOL_VERSIONS
+        .iter()
+        .find(|v| **v == requested)
+        .copied()
+        .unwrap_or(PROTOCOL_VERSION)
+}
+
 //
 // Initialization
 //
@@ -47,10 +61,24 @@ pub struct InitializeResult {
 pub struct ClientCapabilities {
     #[serde(default, skip_serializing_if = "HashMap::is_empty")]
     pub experimental: HashMap<String, Value>,
-    #[serde(default, skip_serializing_if = "HashMap::is_empty")]
-    pub sampling: HashMap<String, Value>,
-    #[serde(default)]
-    pub roots: RootCapabilities,
+    /// Present if the client supports sampling (its value is usually `{}`)
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub sampling: Option<Value>,
+    /// Present if the client exposes filesystem roots
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub roots: Option<RootCapabilities>,
+}
+
+impl ClientCapabilities {
+    /// Check if the client can handle `sampling/createMessage` requests
+    pub fn supports_sampling(&self) -> bool {
+        self.sampling.is_some()
+    }
+
+    /// Check if the client can answer `roots/list` requests
+    pub fn supports_roots(&self) -> bool {
+        self.roots.is_some()
+    }
 }
 
 #[derive(Debug, Clone, Serialize, Deserialize, Default)]
@@ -426,6 +454,27 @@ mod tests {
         assert!(json.contains("\"isError\":true"));
     }
 
+    #[test]
+    fn test_negotiate_protocol_version() {
+        assert_eq!(negotiate_protocol_version("2025-03-26"), "2025-03-26");
+        assert_eq!(negotiate_protocol_version("2024-11-05"), "2024-11-05");
+        assert_eq!(negotiate_protocol_version("1999-01-01"), PROTOCOL_VERSION);
+    }
+
+    #[test]
+    fn test_client_capabilities_presence() {
+        let caps: ClientCapabilities =
+            serde_json::from_value(serde_json::json!({ "sampling": {} })).unwrap();
+        assert!(caps.supports_sampling());
+        assert!(!caps.supports_roots());
+
+        let caps: ClientCapabilities =
+            serde_json::from_value(serde_json::json!({ "roots": { "listChanged": true } }))
+                .unwrap();
+        assert!(!caps.supports_sampling());
+        assert!(caps.roots.unwrap().list_changed);
+    }
+
     #[test]
     fn test_content_serialization() {
         let text = Content::text("hello");
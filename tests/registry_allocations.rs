@@ -0,0 +1,106 @@
+//! Allocations per HTTP request with a shared tool registry
+//!
+//! Counting needs a global allocator, which would apply to every test in
+//! the binary it's declared in, so this gets an integration test of its own.
+
+use serde_json::Value;
+use sml_mcps::{
+    CallToolResult, HttpTransport, RegistryHandle, Result, Server, ServerConfig, Tool, ToolEnv,
+};
+use std::alloc::{GlobalAlloc, Layout, System};
+use std::cell::Cell;
+use std::sync::{Arc, Mutex};
+
+thread_local! {
+    static ALLOCATIONS: Cell<usize> = const { Cell::new(0) };
+}
+
+/// Counts allocations made on each thread
+struct CountingAlloc;
+
+unsafe impl GlobalAlloc for CountingAlloc {
+    unsafe fn alloc(&self, layout: Layout) -> *mut u8 {
+        let _ = ALLOCATIONS.try_with(|count| count.set(count.get() + 1));
+        unsafe { System.alloc(layout) }
+    }
+
+    unsafe fn dealloc(&self, ptr: *mut u8, layout: Layout) {
+        unsafe { System.dealloc(ptr, layout) }
+    }
+}
+
+#[global_allocator]
+static ALLOCATOR: CountingAlloc = CountingAlloc;
+
+/// A tool with a schema, so registering it costs some allocations
+struct NamedTool(&'static str);
+
+impl Tool<()> for NamedTool {
+    fn name(&self) -> &str {
+        self.0
+    }
+    fn description(&self) -> &str {
+        "Do nothing"
+    }
+    fn schema(&self) -> Value {
+        serde_json::json!({
+            "type": "object",
+            "properties": { "message": { "type": "string" } }
+        })
+    }
+    fn execute(&self, _args: Value, _ctx: &mut (), _env: &ToolEnv) -> Result<CallToolResult> {
+        Ok(CallToolResult::text("done"))
+    }
+}
+
+fn setup(server: &mut Server<()>) -> Result<()> {
+    for name in ["echo", "notify", "slow", "search", "fetch"] {
+        server.add_tool(NamedTool(name))?;
+    }
+    Ok(())
+}
+
+/// Average allocations for one `tools/list` request, building its server
+/// from scratch or from the shared registry
+fn allocations_per_request(shared: bool) -> usize {
+    const REQUESTS: usize = 200;
+
+    let config = ServerConfig {
+        strict_lifecycle: false,
+        ..Default::default()
+    };
+    let registry = RegistryHandle::default();
+    let mut template = Server::new(config.clone());
+    setup(&mut template).unwrap();
+    registry.replace(template.registry().clone());
+
+    let before = ALLOCATIONS.with(Cell::get);
+    for _ in 0..REQUESTS {
+        let mut server = if shared {
+            Server::with_registry(config.clone(), registry.snapshot())
+        } else {
+            let mut server = Server::new(config.clone());
+            setup(&mut server).unwrap();
+            server
+        };
+        let list = r#"{"jsonrpc":"2.0","id":1,"method":"tools/list"}"#;
+        let transport = Arc::new(Mutex::new(HttpTransport::new(list.to_string())));
+        server.process_one(transport, &mut ()).unwrap();
+    }
+    (ALLOCATIONS.with(Cell::get) - before) / REQUESTS
+}
+
+#[test]
+fn test_shared_registry_saves_setup_allocations() {
+    let rebuilt = allocations_per_request(false);
+    let shared = allocations_per_request(true);
+
+    // Registering tools costs allocations for every tool, which sharing skips
+    assert!(shared > 0);
+    assert!(
+        shared + 5 <= rebuilt,
+        "rebuilt {} vs shared {}",
+        rebuilt,
+        shared
+    );
+}